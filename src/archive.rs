@@ -0,0 +1,101 @@
+use std::io::{BufRead, BufReader, Cursor, Read, Seek};
+use std::path::{Path, PathBuf};
+use std::time::SystemTime;
+
+// An image inside a ZIP archive is loaded under a path that carries on past the archive's own,
+// e.g. `/scans/batch.zip/ch1/001.jpg`, so names, filters and output naming treat it like a file.
+// Reading one decompresses that entry into memory; nothing is extracted to disk.
+
+pub const ARCHIVE_EXTENSION: &str = "zip";
+
+// Anything a source image can be decoded from: a buffered file, or an entry's bytes
+pub trait Source: BufRead + Seek {}
+
+impl<T: BufRead + Seek> Source for T {}
+
+// Paths of the entries in `archive` with a supported image extension, in natural order.
+// Folders and the `__MACOSX` resource forks that macOS adds to archives are left out.
+pub fn image_paths(archive: &Path) -> Result<Vec<PathBuf>, Box<dyn std::error::Error>> {
+    let mut zip = zip::ZipArchive::new(BufReader::new(std::fs::File::open(archive)?))?;
+    let mut paths = Vec::new();
+    for idx in 0..zip.len() {
+        let entry = zip.by_index_raw(idx)?;
+        // Names that would climb out of the archive, like `../x.jpg`, are skipped
+        let Some(name) = entry.enclosed_name() else {
+            continue;
+        };
+        let resource_fork = name.starts_with("__MACOSX")
+            || name
+                .file_name()
+                .is_some_and(|file| file.to_string_lossy().starts_with("._"));
+        if !entry.is_dir() && !resource_fork && crate::has_supported_extension(&name) {
+            paths.push(archive.join(name));
+        }
+    }
+    paths.sort_by(|a, b| crate::scan::natural_cmp(&a.to_string_lossy(), &b.to_string_lossy()));
+    Ok(paths)
+}
+
+// The archive `path` lies inside and the name of its entry there, or None for an ordinary file
+pub fn split(path: &Path) -> Option<(&Path, String)> {
+    let archive = path.ancestors().skip(1).find(|ancestor| {
+        ancestor
+            .extension()
+            .is_some_and(|ext| ext.eq_ignore_ascii_case(ARCHIVE_EXTENSION))
+            && ancestor.is_file()
+    })?;
+    // Entry names always use `/`, whatever the platform's separator
+    let entry = path
+        .strip_prefix(archive)
+        .ok()?
+        .components()
+        .map(|component| component.as_os_str().to_string_lossy())
+        .collect::<Vec<_>>()
+        .join("/");
+    Some((archive, entry))
+}
+
+// Open a source image for reading, decompressing it first when it's inside an archive
+pub fn open(path: &Path) -> std::io::Result<Box<dyn Source>> {
+    match split(path) {
+        Some((archive, entry)) => Ok(Box::new(Cursor::new(read_entry(archive, &entry)?))),
+        None => Ok(Box::new(BufReader::new(std::fs::File::open(path)?))),
+    }
+}
+
+// Size and modification time of a source image. An entry reports its uncompressed size and,
+// since ZIP times carry no time zone, the archive's own modification time.
+pub fn metadata(path: &Path) -> std::io::Result<(u64, Option<SystemTime>)> {
+    match split(path) {
+        Some((archive, entry)) => {
+            let size = open_archive(archive)?
+                .by_name(&entry)
+                .map_err(std::io::Error::other)?
+                .size();
+            Ok((size, std::fs::metadata(archive)?.modified().ok()))
+        }
+        None => {
+            let metadata = std::fs::metadata(path)?;
+            Ok((metadata.len(), metadata.modified().ok()))
+        }
+    }
+}
+
+// Whether a source image is still there. An entry counts while its archive does; one that was
+// rewritten without it fails when the entry is read instead.
+pub fn exists(path: &Path) -> bool {
+    path.is_file() || split(path).is_some()
+}
+
+fn read_entry(archive: &Path, name: &str) -> std::io::Result<Vec<u8>> {
+    let mut zip = open_archive(archive)?;
+    let mut entry = zip.by_name(name).map_err(std::io::Error::other)?;
+    let mut bytes = Vec::new();
+    entry.read_to_end(&mut bytes)?;
+    Ok(bytes)
+}
+
+fn open_archive(archive: &Path) -> std::io::Result<zip::ZipArchive<BufReader<std::fs::File>>> {
+    zip::ZipArchive::new(BufReader::new(std::fs::File::open(archive)?))
+        .map_err(std::io::Error::other)
+}
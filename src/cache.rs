@@ -0,0 +1,41 @@
+use std::hash::{DefaultHasher, Hash, Hasher};
+use std::path::{Path, PathBuf};
+use std::time::UNIX_EPOCH;
+
+// Bump when the thumbnail encoding changes so old entries stop matching
+const CACHE_VERSION: u32 = 1;
+
+// Build a cache key from the source path, its modification time and size, plus whatever
+// generation parameters affect the output. Editing or replacing the file changes the key.
+pub fn key(path: &Path, params: impl Hash) -> Option<u64> {
+    let (size, modified) = crate::archive::metadata(path).ok()?;
+    let modified = modified?.duration_since(UNIX_EPOCH).ok()?.as_nanos();
+
+    let mut hasher = DefaultHasher::new();
+    CACHE_VERSION.hash(&mut hasher);
+    path.hash(&mut hasher);
+    modified.hash(&mut hasher);
+    size.hash(&mut hasher);
+    params.hash(&mut hasher);
+    Some(hasher.finish())
+}
+
+pub fn load(key: u64) -> Option<Vec<u8>> {
+    std::fs::read(entry_path(key)?).ok()
+}
+
+pub fn store(key: u64, data: &[u8]) -> std::io::Result<()> {
+    let path = entry_path(key).ok_or(std::io::ErrorKind::NotFound)?;
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent)?;
+    }
+    std::fs::write(path, data)
+}
+
+fn entry_path(key: u64) -> Option<PathBuf> {
+    dirs::cache_dir().map(|dir| {
+        dir.join("irs")
+            .join("thumbnails")
+            .join(format!("{:016x}.jpg", key))
+    })
+}
@@ -0,0 +1,248 @@
+use crate::{
+    archive, expand_pages, placeholder_item, process_images_sync, read_image_info, scan,
+    summarize_names, Flip, FolderStructure, ImageItem, OutputFormat, OutputOptions, SplitMode,
+    MAX_SPLITS,
+};
+use std::path::PathBuf;
+use std::sync::atomic::AtomicBool;
+
+const USAGE: &str = "\
+Usage: irs --input <folder> --output <folder> [options]
+
+Splits every image in the input folder and writes the pieces to a subfolder of the output folder.
+Without any arguments the window opens instead.
+
+Options:
+  --input <folder>     Folder of images to process, or a ZIP archive of them
+  --output <folder>    Where the output subfolder is created
+  --split <mode>       vertical (left/right, the default), horizontal (top/bottom)
+                       or quadrants (2 × 2)
+  --splits <n>         Pieces per image, 2 to 10
+  --flip-left <flip>   none (the default), horizontal, vertical or both: mirrors the left
+                       (top) half of a two-way split
+  --flip-right <flip>  The same for the right (bottom) half
+  --format <format>    jpeg (the default) or png
+  --quality <n>        JPEG quality, 1 to 100
+  --dpi <n>            Resolution stored in the output files
+  --prefix <text>      Text in front of each output number
+  --template <text>    Output names from {seq}, {side}, {orig}, {date} and {width},
+                       e.g. {orig}_{side}; needs {seq} or {orig}
+  --start <n>          Number given to the first image
+  --recursive          Also process images in subfolders of the input folder
+  --mirror-folders     Put each source folder's pieces in a subfolder named after it
+  --number-per-folder  With --mirror-folders, start the numbering again in each subfolder
+  --zip                Write everything into one ZIP file instead of a folder
+  --manifest           Write manifest.csv listing each source and its output files
+  --comment <text>     Write the text and the source file name into each JPEG's comment
+  --help               Show this message
+";
+
+// Exit codes: a run with failures is told apart from a command line that couldn't be understood
+const EXIT_OK: i32 = 0;
+const EXIT_FAILED: i32 = 1;
+const EXIT_USAGE: i32 = 2;
+
+#[derive(Debug, PartialEq)]
+pub struct CliArgs {
+    pub input: PathBuf,
+    pub output: PathBuf,
+    pub recursive: bool,
+    pub options: OutputOptions,
+}
+
+// Parse the arguments after the program name. Ok(None) means help was asked for.
+// Anything not given keeps the defaults of a fresh install, not the window's saved settings,
+// so a script behaves the same on every machine.
+pub fn parse_args(args: &[String]) -> Result<Option<CliArgs>, String> {
+    let mut input = None;
+    let mut output = None;
+    let mut recursive = false;
+    let mut options = OutputOptions::default();
+
+    let mut args = args.iter();
+    while let Some(flag) = args.next() {
+        let mut value = || args.next().ok_or_else(|| format!("{} needs a value", flag));
+        match flag.as_str() {
+            "--help" | "-h" => return Ok(None),
+            "--input" => input = Some(PathBuf::from(value()?)),
+            "--output" => output = Some(PathBuf::from(value()?)),
+            "--recursive" => recursive = true,
+            "--mirror-folders" => options.folder_structure = FolderStructure::MirrorSource,
+            "--number-per-folder" => options.number_per_folder = true,
+            "--zip" => options.zip_output = true,
+            "--manifest" => options.write_manifest = true,
+            "--comment" => {
+                options.embed_comment = true;
+                options.comment = value()?.clone();
+            }
+            "--split" => {
+                options.split_mode = match value()?.as_str() {
+                    "vertical" => SplitMode::Vertical,
+                    "horizontal" => SplitMode::Horizontal,
+                    "quadrants" => SplitMode::Quadrants,
+                    other => return Err(format!("Unknown split mode: {}", other)),
+                }
+            }
+            "--flip-left" => options.flip_left = parse_flip(value()?)?,
+            "--flip-right" => options.flip_right = parse_flip(value()?)?,
+            "--splits" => {
+                options.splits = parse_number(flag, value()?, 2, MAX_SPLITS as usize)? as u32
+            }
+            "--format" => {
+                options.format = match value()?.as_str() {
+                    "jpeg" | "jpg" => OutputFormat::Jpeg,
+                    "png" => OutputFormat::Png,
+                    other => return Err(format!("Unknown format: {}", other)),
+                }
+            }
+            "--quality" => options.quality = parse_number(flag, value()?, 1, 100)? as u8,
+            "--dpi" => options.dpi = parse_number(flag, value()?, 1, u16::MAX as usize)? as u16,
+            "--prefix" => options.prefix = value()?.clone(),
+            "--template" => options.name_template = value()?.clone(),
+            "--start" => options.start_index = parse_number(flag, value()?, 0, usize::MAX)?,
+            other => return Err(format!("Unknown option: {}", other)),
+        }
+    }
+
+    Ok(Some(CliArgs {
+        input: input.ok_or("--input is required")?,
+        output: output.ok_or("--output is required")?,
+        recursive,
+        options,
+    }))
+}
+
+fn parse_flip(value: &str) -> Result<Flip, String> {
+    match value {
+        "none" => Ok(Flip::None),
+        "horizontal" => Ok(Flip::Horizontal),
+        "vertical" => Ok(Flip::Vertical),
+        "both" => Ok(Flip::Both),
+        other => Err(format!("Unknown flip: {}", other)),
+    }
+}
+
+fn parse_number(flag: &str, value: &str, min: usize, max: usize) -> Result<usize, String> {
+    value
+        .parse::<usize>()
+        .ok()
+        .filter(|number| (min..=max).contains(number))
+        .ok_or_else(|| format!("{} must be a number from {} to {}", flag, min, max))
+}
+
+// Run a batch without the window, printing one line per image. Returns the process exit code.
+pub fn run(args: &[String]) -> i32 {
+    let args = match parse_args(args) {
+        Ok(Some(args)) => args,
+        Ok(None) => {
+            print!("{}", USAGE);
+            return EXIT_OK;
+        }
+        Err(e) => {
+            eprintln!("irs: {}\n\n{}", e, USAGE);
+            return EXIT_USAGE;
+        }
+    };
+
+    // An archive is read in place; its subfolders are always included
+    let is_archive = args.input.is_file()
+        && args
+            .input
+            .extension()
+            .is_some_and(|ext| ext.eq_ignore_ascii_case(archive::ARCHIVE_EXTENSION));
+    let paths = if is_archive {
+        match archive::image_paths(&args.input) {
+            Ok(paths) => paths,
+            Err(e) => {
+                eprintln!("irs: could not read {}: {}", args.input.display(), e);
+                return EXIT_FAILED;
+            }
+        }
+    } else {
+        scan::collect_images(&args.input, args.recursive)
+    };
+    if paths.is_empty() {
+        eprintln!("irs: no supported images in {}", args.input.display());
+        return EXIT_FAILED;
+    }
+
+    // Sizes decide which images are split and into how many parts, as when loading into the window
+    let mut unreadable = Vec::new();
+    // A multi-page TIFF is processed page by page, each page numbered like a separate image
+    let images: Vec<ImageItem> = paths
+        .into_iter()
+        .flat_map(expand_pages)
+        .filter_map(
+            |(path, page)| match read_image_info(&path, page, args.options.auto_orient) {
+                Ok(info) => Some((path, page, info)),
+                Err(e) => {
+                    eprintln!("✗ {}: {}", path.display(), e);
+                    unreadable.push(path.display().to_string());
+                    None
+                }
+            },
+        )
+        .enumerate()
+        .map(|(id, (path, page, info))| ImageItem {
+            page,
+            width: info.width,
+            height: info.height,
+            file_size: info.file_size,
+            modified: info.modified,
+            captured: info.captured,
+            orientation: info.orientation,
+            ..placeholder_item(id, path)
+        })
+        .collect();
+
+    let total = images.len();
+    let result = process_images_sync(
+        images,
+        None,
+        args.output,
+        args.options,
+        std::sync::Arc::new(AtomicBool::new(false)),
+        |done, total, line| println!("[{}/{}] {}", done, total, line.text),
+    );
+
+    match result {
+        Ok(summary) => {
+            let mut message = format!(
+                "Processed {} of {} images into {}",
+                summary.processed,
+                total,
+                summary.folder.display()
+            );
+            if summary.stats.skipped > 0 {
+                message.push_str(&format!(
+                    ", skipped {} existing files",
+                    summary.stats.skipped
+                ));
+            }
+            if summary.stats.renamed > 0 {
+                message.push_str(&format!(
+                    ", renamed {} to avoid overwriting",
+                    summary.stats.renamed
+                ));
+            }
+            println!("{}", message);
+
+            let mut failed = unreadable;
+            failed.extend(summary.failed);
+            if failed.is_empty() {
+                EXIT_OK
+            } else {
+                eprintln!(
+                    "irs: {} images failed: {}",
+                    failed.len(),
+                    summarize_names(&failed)
+                );
+                EXIT_FAILED
+            }
+        }
+        Err(e) => {
+            eprintln!("irs: {}", e);
+            EXIT_FAILED
+        }
+    }
+}
@@ -0,0 +1,41 @@
+use std::path::PathBuf;
+
+// The error a decoder or encoder crate gave, kept as the source of a Decode or Encode error
+pub type Cause = Box<dyn std::error::Error + Send + Sync>;
+
+// Everything that can go wrong turning source images into previews and output files. The
+// messages name the problem only; the window and the command line add what was being done.
+#[derive(Debug, thiserror::Error)]
+pub enum IrsError {
+    // Reading a source or writing an output file
+    #[error(transparent)]
+    Io(#[from] std::io::Error),
+    // A source that isn't an image IRS can read, or whose data is damaged
+    #[error("{0}")]
+    Decode(#[source] Cause),
+    // A preview, piece, PDF or archive entry that couldn't be encoded
+    #[error("{0}")]
+    Encode(#[source] Cause),
+    // Encoded bytes that lack the markers the DPI, ICC profile or comment are written into
+    #[error("{0}")]
+    InvalidJpeg(&'static str),
+    // The output folder, one of its subfolders or the ZIP file couldn't be made
+    #[error("Failed to create {}: {source}", path.display())]
+    OutputDir {
+        path: PathBuf,
+        source: std::io::Error,
+    },
+    // A run stopped before writing anything: invalid settings, clashing names or missing sources
+    #[error("{0}")]
+    Refused(String),
+}
+
+impl IrsError {
+    pub fn decode(cause: impl Into<Cause>) -> Self {
+        IrsError::Decode(cause.into())
+    }
+
+    pub fn encode(cause: impl Into<Cause>) -> Self {
+        IrsError::Encode(cause.into())
+    }
+}
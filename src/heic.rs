@@ -0,0 +1,48 @@
+use crate::error::IrsError;
+use libheif_rs::{ColorSpace, HeifContext, LibHeif, RgbChroma};
+use std::io::Read;
+use std::path::Path;
+
+// Major brands that mark an ISO-BMFF file as a HEIF still image
+const HEIF_BRANDS: &[&[u8; 4]] = &[
+    b"heic", b"heix", b"hevc", b"hevx", b"heim", b"heis", b"mif1", b"msf1",
+];
+
+// HEIF files start with an `ftyp` box, so check its brand instead of trusting the extension
+pub fn is_heif(path: &Path) -> bool {
+    let mut header = [0u8; 12];
+    let read = std::fs::File::open(path).and_then(|mut file| file.read_exact(&mut header));
+    read.is_ok() && &header[4..8] == b"ftyp" && HEIF_BRANDS.iter().any(|b| &header[8..12] == *b)
+}
+
+// Decode the primary image as 8-bit RGB. libheif already applies the rotation and mirroring
+// stored in the container, so callers must not apply the EXIF orientation on top.
+pub fn decode(path: &Path) -> Result<image::DynamicImage, IrsError> {
+    let context = HeifContext::read_from_file(&path.to_string_lossy()).map_err(IrsError::decode)?;
+    let handle = context.primary_image_handle().map_err(IrsError::decode)?;
+    let decoded = LibHeif::new()
+        .decode(&handle, ColorSpace::Rgb(RgbChroma::Rgb), None)
+        .map_err(IrsError::decode)?;
+
+    let planes = decoded.planes();
+    let plane = planes
+        .interleaved
+        .ok_or_else(|| IrsError::decode("HEIF image has no RGB plane"))?;
+    let row_bytes = plane.width as usize * 3;
+
+    // Rows may be padded out to `stride`, so copy them one at a time
+    let mut pixels = Vec::with_capacity(row_bytes * plane.height as usize);
+    for row in plane.data.chunks(plane.stride).take(plane.height as usize) {
+        pixels.extend_from_slice(&row[..row_bytes]);
+    }
+    let rgb = image::RgbImage::from_raw(plane.width, plane.height, pixels)
+        .ok_or_else(|| IrsError::decode("HEIF image data is truncated"))?;
+    Ok(image::DynamicImage::ImageRgb8(rgb))
+}
+
+// Size of the primary image after the container's transformations, without decoding it
+pub fn dimensions(path: &Path) -> Result<(u32, u32), IrsError> {
+    let context = HeifContext::read_from_file(&path.to_string_lossy()).map_err(IrsError::decode)?;
+    let handle = context.primary_image_handle().map_err(IrsError::decode)?;
+    Ok((handle.width(), handle.height()))
+}
@@ -1,16 +1,116 @@
 use dioxus::desktop::tao::window::Icon;
 use dioxus::desktop::{Config, WindowBuilder};
 use dioxus::events::KeyboardEvent;
+use dioxus::html::input_data::MouseButton;
+use dioxus::html::HasFileData;
 use dioxus::prelude::*;
-use image::GenericImageView;
+use error::IrsError;
+use image::{GenericImageView, ImageFormat};
 use keyboard_types::Key;
-use std::path::PathBuf;
+use rayon::prelude::*;
+use recent::RecentBatch;
+use serde::{Deserialize, Serialize};
+use settings::Settings;
+use std::collections::{HashMap, HashSet};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+
+mod archive;
+mod cache;
+mod cli;
+mod error;
+#[cfg(feature = "heic")]
+mod heic;
+mod manifest;
+mod pdf;
+mod project;
+mod recent;
+mod scan;
+mod settings;
+mod tiff_pages;
 
 const MAIN_CSS: Asset = asset!("/src/main.css");
 const THUMBNAIL_SIZE: u32 = 200;
+// Used instead of THUMBNAIL_SIZE when high-resolution previews are turned on for large zoom levels
+const HIRES_THUMBNAIL_SIZE: u32 = 600;
+// Images above this many megapixels get the memory-saving preview path (see `open_for_thumbnail`).
+// 40 MP is roughly an A3 page scanned at 600 DPI, about 120 MB once decoded to RGB.
+const DEFAULT_LARGE_IMAGE_MEGAPIXELS: u32 = 40;
+const MAX_LARGE_IMAGE_MEGAPIXELS: u32 = 1000;
+// Minimum preview card width in CSS pixels; the grid stretches cards to fill each row
+const DEFAULT_CARD_SIZE: u32 = 150;
+const MIN_CARD_SIZE: u32 = 100;
+const MAX_CARD_SIZE: u32 = 400;
+const DEFAULT_THUMBNAIL_QUALITY: u8 = 85;
+// Matches the `gap` of `#image-preview` in main.css
+const GRID_GAP: f64 = 12.0;
+// The lightbox shows the full-resolution image, so it's re-encoded close to the source quality
+const LIGHTBOX_QUALITY: u8 = 92;
+// Longest edge of each piece shown by PREVIEW SPLIT; the pieces themselves are cut at full size
+const SPLIT_PREVIEW_SIZE: u32 = 1200;
+// Lightbox zoom, from fitting the window up to 8× that; each wheel notch zooms by a quarter
+const MAX_LIGHTBOX_ZOOM: f64 = 8.0;
+const LIGHTBOX_ZOOM_STEP: f64 = 1.25;
+const DEFAULT_COMMENT: &str = "Processed by IRS";
+// How long a tone or preview slider has to rest before the previews are regenerated for its value
+const LIVE_PREVIEW_DELAY_MS: u64 = 150;
+// Pointer travel in CSS pixels before a press on the empty preview becomes a rubber-band selection
+const RUBBER_BAND_THRESHOLD: f64 = 4.0;
+#[cfg(not(feature = "heic"))]
+const SUPPORTED_EXTENSIONS: &[&str] = &["jpg", "jpeg", "png", "tif", "tiff", "webp"];
+#[cfg(feature = "heic")]
+const SUPPORTED_EXTENSIONS: &[&str] =
+    &["jpg", "jpeg", "png", "tif", "tiff", "webp", "heic", "heif"];
+const UNDO_LIMIT: usize = 50;
+// Oldest toasts are dropped once this many are on screen
+const MAX_NOTIFICATIONS: usize = 5;
+// Oldest lines of the processing log are dropped past this
+const MAX_LOG_LINES: usize = 500;
+const DEFAULT_QUALITY: u8 = 100;
+const DEFAULT_SPLITS: u32 = 2;
+const MAX_SPLITS: u32 = 10;
+const DEFAULT_SPLIT_RATIO: f32 = 0.5;
+// Keep the divider away from the edges so neither half can end up as a sliver
+const MIN_SPLIT_RATIO: f32 = 0.05;
+const MAX_SPLIT_RATIO: f32 = 0.95;
+const MAX_OVERLAP: u32 = 1000;
+const DEFAULT_DPI: u16 = 300;
+const DPI_OPTIONS: &[u16] = &[72, 150, 300, 600];
+// Offered when one image is given its own quality; the batch setting remains a slider
+const QUALITY_OVERRIDE_OPTIONS: &[u8] = &[100, 95, 90, 85, 80, 70, 60];
+const DEFAULT_START_INDEX: usize = 1;
+const DEFAULT_PADDING: usize = 2;
+const MAX_PADDING: usize = 10;
+const DEFAULT_SUBFOLDER: &str = "SPL";
+const MAX_THREADS: usize = 64;
+// Luma at or above this counts as paper when trimming borders
+const DEFAULT_TRIM_THRESHOLD: u8 = 235;
+// Scanner dust shouldn't stop a trim, so an edge line only counts as content above this share of dark pixels
+const TRIM_NOISE_FRACTION: f32 = 0.01;
+// Share of the length, centred, that gutter detection searches, in percent
+const DEFAULT_GUTTER_WINDOW: u32 = 20;
+const MAX_GUTTER_WINDOW: u32 = 80;
+// How much darker than the searched band's average (in luma levels) a gutter has to be to be trusted
+const GUTTER_MIN_CONTRAST: f32 = 10.0;
+// Longest side, in pixels, offered when output size limiting is first switched on
+const DEFAULT_MAX_DIMENSION: u32 = 4000;
+// Brightness is added to every channel and contrast is a percentage; both range from minus to plus this
+const MAX_TONE_ADJUSTMENT: i32 = 100;
+// Transparent scans are flattened onto paper white, not the black a plain RGB conversion gives
+const DEFAULT_BACKGROUND: [u8; 3] = [255, 255, 255];
+const WINDOW_TITLE: &str = "IRS - IMAGE RENAME SPLIT";
+const INVALID_FILENAME_CHARS: &[char] = &['/', '\\', ':', '*', '?', '"', '<', '>', '|'];
+// Placeholders recognised in the output file name template
+const NAME_TEMPLATE_TOKENS: &[&str] = &["seq", "side", "orig", "date", "width"];
 
 
 fn main() {
+    // Any arguments mean a scripted run; the window only opens without them
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    if !args.is_empty() {
+        std::process::exit(cli::run(&args));
+    }
+
     let img = image::load_from_memory(include_bytes!("../assets/icon.png"))
         .expect("Failed to load icon")
         .into_rgba8();
@@ -20,7 +120,7 @@ fn main() {
             Config::default()
                 .with_window(
                 WindowBuilder::new()
-                    .with_title("IRS - IMAGE RENAME SPLIT")
+                    .with_title(WINDOW_TITLE)
                     .with_maximized(true),
             )
                 .with_icon(Icon::from_rgba(img.to_vec(), width, height).unwrap())
@@ -29,13 +129,48 @@ fn main() {
         .launch(App);
 }
 
-#[derive(Clone, Debug, PartialEq, Eq)]
+#[derive(Clone, Debug, PartialEq)]
 struct ImageItem {
     id: usize,
     path: PathBuf,
+    // Which page of a multi-page TIFF this card is (0-based); None for a file holding one image
+    page: Option<usize>,
+    // Empty while the thumbnail is still being generated
     thumbnail_base64: String,
+    // Clockwise rotation in degrees (0, 90, 180 or 270) applied before splitting
+    rotation: u16,
+    // Where a two-way split cuts, as a fraction of the (rotated) width or height
+    split_ratio: f32,
+    // Split this image differently from the rest of the batch; None follows the global setting
+    split_mode: Option<SplitMode>,
+    // JPEG quality and DPI for this image's output only; None follows the global setting
+    quality: Option<u8>,
+    dpi: Option<u16>,
+    // Upright pixel size (after EXIF orientation, if honoured, and before `rotation`) and size on disk
+    width: u32,
+    height: u32,
+    file_size: u64,
+    // None when the file system doesn't report modification times
+    modified: Option<std::time::SystemTime>,
+    // EXIF capture date as `YYYY:MM:DD HH:MM:SS`, None when the file doesn't record one
+    captured: Option<String>,
+    // EXIF orientation tag stored in the file (1-8), 1 when there is none
+    orientation: u32,
 }
 
+// File details gathered while loading, without decoding the pixels
+struct ImageInfo {
+    width: u32,
+    height: u32,
+    file_size: u64,
+    modified: Option<std::time::SystemTime>,
+    captured: Option<String>,
+    orientation: u32,
+}
+
+// A file's base64 thumbnail and details, or why it couldn't be loaded
+type LoadResult = Result<(String, ImageInfo), String>;
+
 #[derive(Clone, Debug, PartialEq)]
 struct Notification {
     message: String,
@@ -51,41 +186,950 @@ enum NotificationType {
     Processing,
 }
 
+// How previews are generated; part of the thumbnail cache key
+#[derive(Clone, Debug, PartialEq, Copy, Hash)]
+struct ThumbnailOptions {
+    // Longest edge in pixels
+    size: u32,
+    quality: u8,
+    // Whether the EXIF orientation is applied, as for the output
+    auto_orient: bool,
+    // The output's tone adjustment, so previews show what will be written
+    brightness: i32,
+    contrast: i32,
+    // What transparent areas are shown on, as in JPEG output
+    background: [u8; 3],
+    // Above this size previews are made without holding the full image (see `open_for_thumbnail`)
+    large_image_megapixels: u32,
+    fit: PreviewFit,
+}
+
+// Result of one image in the processing log
+#[derive(Clone, Debug, PartialEq)]
+struct LogLine {
+    success: bool,
+    text: String,
+}
+
+// Where newly loaded images go in the list
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum Placement {
+    // Start a new set
+    Replace,
+    // After the current images
+    Append,
+    // At this index, shifting the images from there on back
+    InsertAt(usize),
+}
+
+// Main actions bound to keyboard shortcuts. App records the key press and Controls runs the action.
+#[derive(Clone, Debug, PartialEq, Copy)]
+enum Shortcut {
+    Open,
+    Clear,
+    RenameSplit,
+}
+
+#[derive(Clone, Debug, PartialEq, Copy, Serialize, Deserialize)]
+enum SplitMode {
+    // Left and right halves
+    Vertical,
+    // Top and bottom halves
+    Horizontal,
+    // 4-up sheets: halved both ways and numbered top-left, top-right, bottom-left, bottom-right.
+    // The parts setting and split position don't apply.
+    Quadrants,
+}
+
+// Which side of a left/right split comes first in the numbering
+#[derive(Clone, Debug, PartialEq, Copy, Serialize, Deserialize)]
+enum ReadingOrder {
+    LeftToRight,
+    // Manga and right-to-left scripts: the right half is `_1`
+    RightToLeft,
+}
+
+// Mirroring applied to one half of a two-way split, e.g. for transparency-film scans whose
+// right page comes out reversed
+#[derive(Clone, Debug, PartialEq, Copy, Serialize, Deserialize)]
+enum Flip {
+    None,
+    // Left and right swapped
+    Horizontal,
+    // Upside down
+    Vertical,
+    // Both at once, the same as turning the half by 180°
+    Both,
+}
+
+// Which pages of the run are written, e.g. for printing both sides of a duplex sheet in two passes.
+// Pages are counted through the whole run, so a filtered run names its files exactly as a full one.
+#[derive(Clone, Debug, PartialEq, Copy, Serialize, Deserialize)]
+enum PageFilter {
+    All,
+    Odd,
+    Even,
+}
+
+impl PageFilter {
+    fn keeps(self, page: usize) -> bool {
+        match self {
+            PageFilter::All => true,
+            PageFilter::Odd => !page.is_multiple_of(2),
+            PageFilter::Even => page.is_multiple_of(2),
+        }
+    }
+}
+
+// How the preview lays out the cards
+#[derive(Clone, Debug, PartialEq, Copy, Serialize, Deserialize)]
+enum ViewMode {
+    // Square cards flowing in rows
+    Grid,
+    // One compact row per image with the full file name
+    List,
+}
+
+// How a preview fills its square: whole and letterboxed, or centre-cropped to the square.
+// Only the previews change; output is always made from the full image.
+#[derive(Clone, Debug, PartialEq, Copy, Hash, Serialize, Deserialize)]
+enum PreviewFit {
+    Contain,
+    Cover,
+}
+
+// What to do when an output file with the same name already exists in the SPL folder
+#[derive(Clone, Debug, PartialEq, Copy, Serialize, Deserialize)]
+enum OverwritePolicy {
+    Overwrite,
+    Skip,
+    // Write next to the existing file as `name_v2.jpg`, `name_v3.jpg`, ...
+    RenameWithSuffix,
+}
+
+#[derive(Clone, Debug, PartialEq, Copy, Serialize, Deserialize)]
+enum OutputFormat {
+    Jpeg,
+    // Lossless, for line art where JPEG artifacts are visible
+    Png,
+}
+
+impl OutputFormat {
+    fn extension(self) -> &'static str {
+        match self {
+            OutputFormat::Jpeg => "jpg",
+            OutputFormat::Png => "png",
+        }
+    }
+}
+
+// Whether the split pieces are also bundled, in order, into one multi-page PDF
+#[derive(Clone, Debug, PartialEq, Copy, Serialize, Deserialize)]
+enum PdfExport {
+    Off,
+    WithImages,
+    // Write only the PDF, no separate image files
+    Only,
+}
+
+// How the output files are laid out inside the run's folder
+#[derive(Clone, Debug, PartialEq, Copy, Serialize, Deserialize)]
+enum FolderStructure {
+    Flat,
+    // One subfolder per source folder, named after it, e.g. `SPL/vol1/001_1.jpg`
+    MirrorSource,
+}
+
+// Everything process_images_sync needs to know about how output files are produced and named
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+struct OutputOptions {
+    split_mode: SplitMode,
+    // Only affects left/right splits; top/bottom pieces are always numbered from the top
+    reading_order: ReadingOrder,
+    // Number of equal strips each image is cut into along the split direction
+    splits: u32,
+    // Pixels each piece extends past its cut lines, so content in the gutter appears on both sides
+    overlap: u32,
+    format: OutputFormat,
+    // JPEG only; PNG is always lossless
+    quality: u8,
+    dpi: u16,
+    prefix: String,
+    // Replaces the `{seq}_{side}` part of output names when set, e.g. `{orig}_{side}`.
+    // See `expand_name_template` for the tokens; the prefix and extension are still added.
+    name_template: String,
+    start_index: usize,
+    padding: usize,
+    overwrite_policy: OverwritePolicy,
+    // Folder created inside the chosen save location to hold the output
+    subfolder: String,
+    // Append the start time, e.g. `SPL_20240115_1432`, so every run gets its own folder
+    timestamp_subfolder: bool,
+    // Write everything into `SPL.zip` (named like the folder would be) instead of a folder
+    zip_output: bool,
+    folder_structure: FolderStructure,
+    // When mirroring source folders, number each subfolder from the start index instead of
+    // counting on across the whole run
+    number_per_folder: bool,
+    // Worker threads used for splitting; 0 picks one per available core
+    threads: usize,
+    pdf_export: PdfExport,
+    // Also write the whole (rotated) page as part 0, e.g. `003_0.jpg`, next to its pieces
+    keep_original: bool,
+    // Don't split at all: each image is written whole as e.g. `003.jpg`, just renumbered
+    rename_only: bool,
+    // Split only images wider than tall; portrait pages such as covers pass through whole as `_1`
+    landscape_only: bool,
+    // Honour the EXIF orientation tag; off for scans whose pixels are already upright
+    auto_orient: bool,
+    // Write single-channel output, roughly halving JPEG size for text scans
+    grayscale: bool,
+    // Crop near-white margins before splitting so the gutter ends up centred
+    auto_trim: bool,
+    trim_threshold: u8,
+    // Cut two-way splits at the darkest band near the centre instead of exactly halfway
+    detect_gutter: bool,
+    gutter_window: u32,
+    // Give both halves of an odd-sized centre cut the same size by dropping the middle column
+    // (row for top/bottom) at index length / 2. Cuts off the centre keep every pixel.
+    balance_halves: bool,
+    // Mirroring of the left and right halves (top and bottom for top/bottom splits) as they lie
+    // in the image, whichever is numbered first. Other cuts have no halves and are left alone.
+    flip_left: Flip,
+    flip_right: Flip,
+    // Shrink any output wider or taller than `max_dimension`, keeping its aspect ratio
+    limit_size: bool,
+    max_dimension: u32,
+    // Kept originals (part 0) aren't pages and are written whatever the filter
+    page_filter: PageFilter,
+    // Tone adjustment for washed-out scans; 0 leaves the pixels as they are
+    brightness: i32,
+    contrast: i32,
+    // Write `manifest.csv` into the output folder, listing each source and the files it became
+    write_manifest: bool,
+    // RGB colour that transparent areas are laid on when writing JPEG, which has no alpha.
+    // PNG output keeps its transparency.
+    background: [u8; 3],
+    // Write `comment` and the source file name into each JPEG's COM segment, for provenance
+    embed_comment: bool,
+    comment: String,
+}
+
+impl Default for OutputOptions {
+    fn default() -> Self {
+        Self {
+            split_mode: SplitMode::Vertical,
+            reading_order: ReadingOrder::LeftToRight,
+            splits: DEFAULT_SPLITS,
+            overlap: 0,
+            format: OutputFormat::Jpeg,
+            quality: DEFAULT_QUALITY,
+            dpi: DEFAULT_DPI,
+            prefix: String::new(),
+            name_template: String::new(),
+            start_index: DEFAULT_START_INDEX,
+            padding: DEFAULT_PADDING,
+            overwrite_policy: OverwritePolicy::Overwrite,
+            subfolder: DEFAULT_SUBFOLDER.to_string(),
+            timestamp_subfolder: false,
+            zip_output: false,
+            folder_structure: FolderStructure::Flat,
+            number_per_folder: false,
+            threads: 0,
+            pdf_export: PdfExport::Off,
+            keep_original: false,
+            rename_only: false,
+            landscape_only: false,
+            auto_orient: true,
+            grayscale: false,
+            auto_trim: false,
+            trim_threshold: DEFAULT_TRIM_THRESHOLD,
+            detect_gutter: false,
+            gutter_window: DEFAULT_GUTTER_WINDOW,
+            balance_halves: false,
+            flip_left: Flip::None,
+            flip_right: Flip::None,
+            limit_size: false,
+            max_dimension: DEFAULT_MAX_DIMENSION,
+            page_filter: PageFilter::All,
+            brightness: 0,
+            contrast: 0,
+            write_manifest: false,
+            background: DEFAULT_BACKGROUND,
+            embed_comment: false,
+            comment: DEFAULT_COMMENT.to_string(),
+        }
+    }
+}
+
+// The failed images of the last run and how to redo them: the same list for numbering, folder and settings
+#[derive(Clone, Debug, PartialEq)]
+struct RetryRun {
+    images: Vec<ImageItem>,
+    ids: HashSet<usize>,
+    save_folder: PathBuf,
+    options: OutputOptions,
+}
+
+// How the overwrite policy treated the output files of one or more images
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+struct WriteStats {
+    skipped: usize,
+    renamed: usize,
+}
+
+// What one image produced: how its files fared, the names written and its pages for the PDF
+struct ImageOutput {
+    stats: WriteStats,
+    files: Vec<String>,
+    pages: Vec<pdf::PdfPage>,
+}
+
+#[derive(Clone, Debug, Default, PartialEq)]
+struct ProcessSummary {
+    processed: usize,
+    stats: WriteStats,
+    // File names of the source images that could not be split, and their item ids
+    failed: Vec<String>,
+    failed_ids: Vec<usize>,
+    // Set when the run was stopped early; the remaining images were left untouched
+    cancelled: bool,
+    // File name of the bundled PDF, if one was written
+    pdf: Option<String>,
+    // The output folder, including any timestamp suffix, or the ZIP file written instead
+    folder: PathBuf,
+}
+
 #[component]
 fn App() -> Element {
-    let images = use_signal(|| Vec::<ImageItem>::new());
+    let mut images = use_signal(|| Vec::<ImageItem>::new());
     let folder_path = use_signal(|| None::<PathBuf>);
     let processing = use_signal(|| false);
-    let notification = use_signal(|| None::<Notification>);
+    let notifications = use_signal(Vec::<Notification>::new);
     let loading_files = use_signal(|| false);
     let drag_source = use_signal(|| None::<usize>);
     let drag_over_id = use_signal(|| None::<usize>);
+    let settings = use_signal(Settings::load);
+    let progress = use_signal(|| None::<(usize, usize)>);
+    let mut history = use_signal(Vec::<Vec<ImageItem>>::new);
+    let selected = use_signal(HashSet::<usize>::new);
+    let dry_run_plan = use_signal(|| None::<Vec<String>>);
+    let mut shortcut = use_signal(|| None::<Shortcut>);
+    // Id of the image open in the full-size lightbox
+    let lightbox = use_signal(|| None::<usize>);
+    // Id of the image whose pieces are shown by PREVIEW SPLIT
+    let split_preview = use_signal(|| None::<usize>);
+    let log = use_signal(Vec::<LogLine>::new);
+    // Cards whose file name doesn't contain this are hidden; the list itself is left alone
+    let filter = use_signal(String::new);
+
+    // Persist every settings change so the next launch starts where this one left off
+    use_effect(move || {
+        // Failing to write the config file shouldn't interrupt the user; defaults are used next time
+        let _ = settings.read().save();
+    });
+
+    // Mirror processing progress in the window title so it can be followed while minimized
+    use_effect(move || {
+        let title = match progress() {
+            Some((done, total)) => progress_title(done, total),
+            None => WINDOW_TITLE.to_string(),
+        };
+        dioxus::desktop::window().set_title(&title);
+    });
+
+    let mut undo = move |_| {
+        if processing() || loading_files() {
+            return;
+        }
+        let previous = history.write().pop();
+        if let Some(previous) = previous {
+            images.set(previous);
+            notify(
+                notifications,
+                "Undid last change".to_string(),
+                NotificationType::Info,
+            );
+        }
+    };
+
+    // Once placeholder cards are on screen they show the progress, so the blocking popup goes away
+    let show_loading_popup = loading_files()
+        && !images
+            .read()
+            .iter()
+            .any(|img| img.thumbnail_base64.is_empty());
 
     rsx! {
         document::Link { rel: "stylesheet", href: MAIN_CSS }
-        Controls {
-            images,
-            folder_path,
-            processing,
-            notification,
-            loading_files,
+        div {
+            id: "app",
+            tabindex: "-1",
+            autofocus: true,
+            onkeydown: move |evt: KeyboardEvent| {
+                let modifiers = evt.modifiers();
+                if !(modifiers.ctrl() || modifiers.meta()) {
+                    return;
+                }
+                match evt.key() {
+                    Key::Character(c) if c.eq_ignore_ascii_case("z") => undo(()),
+                    Key::Character(c) if c.eq_ignore_ascii_case("o") => {
+                        shortcut.set(Some(Shortcut::Open))
+                    }
+                    Key::Character(c) if modifiers.shift() && c.eq_ignore_ascii_case("c") => {
+                        shortcut.set(Some(Shortcut::Clear))
+                    }
+                    Key::Enter => shortcut.set(Some(Shortcut::RenameSplit)),
+                    _ => return,
+                }
+                evt.prevent_default();
+            },
+            Controls {
+                images,
+                folder_path,
+                processing,
+                notifications,
+                loading_files,
+                settings,
+                progress,
+                history,
+                selected,
+                dry_run_plan,
+                shortcut,
+                log,
+                filter,
+            }
+            LogPanel { log }
+            ImagePreview {
+                images,
+                output: settings.read().output.clone(),
+                card_size: settings.read().card_size,
+                view_mode: settings.read().view_mode,
+                thumbnail_options: settings.read().thumbnail_options(),
+                drag_source,
+                drag_over_id,
+                history,
+                selected,
+                processing,
+                loading_files,
+                notifications,
+                log,
+                lightbox,
+                split_preview,
+                filter: filter(),
+            }
+            if lightbox().is_some() {
+                Lightbox {
+                    images,
+                    lightbox,
+                    auto_orient: settings.read().output.auto_orient,
+                    background: settings.read().output.background,
+                }
+            }
+            if split_preview().is_some() {
+                SplitPreviewPopup {
+                    images,
+                    split_preview,
+                    output: settings.read().output.clone(),
+                }
+            }
+            if show_loading_popup {
+                LoadingPopup {}
+            }
+            if let Some(plan) = dry_run_plan() {
+                DryRunPopup { plan, dry_run_plan }
+            }
+            if !notifications.read().is_empty() {
+                div {
+                    id: "notification-stack",
+                    for notif in notifications() {
+                        NotificationPopup {
+                            key: "{notif.id}",
+                            notification: notif,
+                            progress: progress(),
+                        }
+                    }
+                }
+            }
         }
-        ImagePreview {
-            images,
-            drag_source,
-            drag_over_id,
+    }
+}
+
+// Queue a notification; everything except the processing popup dismisses itself after 3 seconds.
+// A processing popup stays until whoever showed it removes it by the returned id.
+fn notify(
+    mut notifications: Signal<Vec<Notification>>,
+    message: String,
+    notification_type: NotificationType,
+) -> u64 {
+    let now = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map(|d| d.as_millis() as u64)
+        .unwrap_or(0);
+
+    let id = {
+        let mut queue = notifications.write();
+        // Messages sent within the same millisecond still need distinct ids for their timers
+        let id = queue.iter().map(|n| n.id + 1).max().unwrap_or(0).max(now);
+        queue.push(Notification {
+            message,
+            notification_type,
+            id,
+        });
+        // The oldest toast makes room, but a processing popup holds the only progress display
+        if queue.len() > MAX_NOTIFICATIONS {
+            if let Some(oldest) = queue
+                .iter()
+                .position(|n| n.notification_type != NotificationType::Processing)
+            {
+                queue.remove(oldest);
+            }
         }
-        if loading_files() {
-            LoadingPopup {}
+        id
+    };
+
+    if notification_type != NotificationType::Processing {
+        spawn(async move {
+            tokio::time::sleep(tokio::time::Duration::from_secs(3)).await;
+            notifications.write().retain(|n| n.id != id);
+        });
+    }
+    id
+}
+
+// Generate thumbnails for `paths` on a blocking thread and put the results into `images`
+// at `placement`: replacing the current items, after them, or in between.
+// Files that can't be loaded are listed in `log` with the reason.
+async fn load_images(
+    paths: Vec<PathBuf>,
+    images: Signal<Vec<ImageItem>>,
+    notifications: Signal<Vec<Notification>>,
+    log: Signal<Vec<LogLine>>,
+    placement: Placement,
+    thumbnail_options: ThumbnailOptions,
+) {
+    let sources = paths.into_iter().map(|path| (path, None)).collect();
+    load_pages(
+        sources,
+        images,
+        notifications,
+        log,
+        placement,
+        thumbnail_options,
+    )
+    .await;
+}
+
+// Like `load_images`, but each file comes with the TIFF page to show. None loads every page of a
+// multi-page TIFF as its own card; Some picks one, as saved in a project.
+async fn load_pages(
+    sources: Vec<(PathBuf, Option<usize>)>,
+    mut images: Signal<Vec<ImageItem>>,
+    notifications: Signal<Vec<Notification>>,
+    mut log: Signal<Vec<LogLine>>,
+    placement: Placement,
+    thumbnail_options: ThumbnailOptions,
+) {
+    let total_files = sources.len();
+    // Continue numbering after the existing items so ids stay unique for reordering and drag
+    let first_id = if placement == Placement::Replace {
+        0
+    } else {
+        images
+            .read()
+            .iter()
+            .map(|img| img.id + 1)
+            .max()
+            .unwrap_or(0)
+    };
+
+    // A cheap header probe weeds out files that only look like images by name,
+    // then a multi-page TIFF is expanded into one entry per page
+    let Ok((valid, invalid)) = tokio::task::spawn_blocking(move || {
+        let (valid, invalid): (Vec<_>, Vec<_>) = sources
+            .into_iter()
+            .partition(|(path, _)| has_image_header(path));
+        let valid = valid
+            .into_iter()
+            .flat_map(|(path, page)| match page {
+                Some(_) => vec![(path, page)],
+                None => expand_pages(path),
+            })
+            .collect::<Vec<_>>();
+        (valid, invalid)
+    })
+    .await
+    else {
+        notify(
+            notifications,
+            "✗ Loading images failed".to_string(),
+            NotificationType::Error,
+        );
+        return;
+    };
+
+    // Show a placeholder card for every file right away; thumbnails fill in as they are generated
+    let placeholders: Vec<ImageItem> = valid
+        .iter()
+        .enumerate()
+        .map(|(idx, (path, page))| ImageItem {
+            page: *page,
+            ..placeholder_item(first_id + idx, path.clone())
+        })
+        .collect();
+    match placement {
+        Placement::Replace => images.set(placeholders),
+        Placement::Append => images.write().extend(placeholders),
+        Placement::InsertAt(idx) => {
+            let mut imgs = images.write();
+            let idx = idx.min(imgs.len());
+            imgs.splice(idx..idx, placeholders);
         }
-        if let Some(notif) = notification() {
-            NotificationPopup {
-                notification: notif,
+    }
+
+    let (tx, mut rx) = tokio::sync::mpsc::unbounded_channel();
+    let generator = tokio::task::spawn_blocking(move || {
+        generate_image_data(valid, first_id, thumbnail_options, tx)
+    });
+
+    let mut loaded = 0usize;
+    let mut failed: Vec<String> = Vec::new();
+    let mut errors: Vec<LogLine> = invalid
+        .iter()
+        .map(|(path, _)| LogLine {
+            success: false,
+            text: format!("✗ {}: not a supported image", path.display()),
+        })
+        .collect();
+    while let Some((id, result)) = rx.recv().await {
+        match result {
+            Ok((thumbnail_base64, info)) => {
+                loaded += 1;
+                if let Some(img) = images.write().iter_mut().find(|img| img.id == id) {
+                    // A card rotated while still loading is getting its own thumbnail already
+                    if img.rotation == 0 {
+                        img.thumbnail_base64 = thumbnail_base64;
+                    }
+                    img.width = info.width;
+                    img.height = info.height;
+                    img.file_size = info.file_size;
+                    img.modified = info.modified;
+                    img.captured = info.captured;
+                    img.orientation = info.orientation;
+                }
             }
+            Err(e) => {
+                let mut imgs = images.write();
+                if let Some(pos) = imgs.iter().position(|img| img.id == id) {
+                    let img = imgs.remove(pos);
+                    errors.push(LogLine {
+                        success: false,
+                        text: format!("✗ {}: {}", item_source(&img), e),
+                    });
+                    failed.push(item_name(&img));
+                }
+            }
+        }
+    }
+    if generator.await.is_err() {
+        notify(
+            notifications,
+            "✗ Loading images failed".to_string(),
+            NotificationType::Error,
+        );
+    }
+
+    let skipped = if invalid.is_empty() {
+        String::new()
+    } else {
+        format!(", skipped {} invalid files", invalid.len())
+    };
+    if loaded == 0 {
+        notify(
+            notifications,
+            format!("✗ No valid images found{}", skipped),
+            NotificationType::Error,
+        );
+    } else {
+        notify(
+            notifications,
+            format!("✓ Loaded {} images{}", loaded, skipped),
+            NotificationType::Success,
+        );
+    }
+
+    // Name the files that were dropped so an unsupported format doesn't go unnoticed
+    if !failed.is_empty() {
+        notify(
+            notifications,
+            format!(
+                "✗ Could not read {} of {} files: {}. The log lists why.",
+                failed.len(),
+                total_files,
+                summarize_names(&failed)
+            ),
+            NotificationType::Error,
+        );
+    }
+    // A corrupt file needs re-scanning, so its full path and the decoder's error go in the log
+    if !errors.is_empty() {
+        log.set(errors);
+    }
+}
+
+// One entry per page of a multi-page TIFF, or just the file when it holds a single image
+fn expand_pages(path: PathBuf) -> Vec<(PathBuf, Option<usize>)> {
+    match tiff_pages::page_count(&path) {
+        1 => vec![(path, None)],
+        pages => (0..pages).map(|page| (path.clone(), Some(page))).collect(),
+    }
+}
+
+// The file name shown for an item. Pages of a multi-page TIFF are told apart as `scan.tif#2`.
+fn item_name(item: &ImageItem) -> String {
+    let name = item.path.file_name().unwrap_or_default().to_string_lossy();
+    match item.page {
+        Some(page) => format!("{}#{}", name, page + 1),
+        None => name.to_string(),
+    }
+}
+
+// The full path of an item's file, with the page of a multi-page TIFF as in `item_name`
+fn item_source(item: &ImageItem) -> String {
+    match item.page {
+        Some(page) => format!("{}#{}", item.path.display(), page + 1),
+        None => item.path.display().to_string(),
+    }
+}
+
+// A card for a file whose thumbnail hasn't been generated yet
+fn placeholder_item(id: usize, path: PathBuf) -> ImageItem {
+    ImageItem {
+        id,
+        path,
+        page: None,
+        thumbnail_base64: String::new(),
+        rotation: 0,
+        split_ratio: DEFAULT_SPLIT_RATIO,
+        split_mode: None,
+        quality: None,
+        dpi: None,
+        width: 0,
+        height: 0,
+        file_size: 0,
+        modified: None,
+        captured: None,
+        orientation: 1,
+    }
+}
+
+// Decode on every core and send each result as soon as it's ready, tagged with the id given to
+// `sources[i]` (first_id + i). An error says why the file couldn't be read.
+fn generate_image_data(
+    sources: Vec<(PathBuf, Option<usize>)>,
+    first_id: usize,
+    thumbnail_options: ThumbnailOptions,
+    tx: tokio::sync::mpsc::UnboundedSender<(usize, LoadResult)>,
+) {
+    sources
+        .into_par_iter()
+        .enumerate()
+        .for_each_with(tx, |tx, (idx, (path_buf, page))| {
+            let loaded = create_thumbnail(&path_buf, page, 0, thumbnail_options)
+                .and_then(|thumbnail| {
+                    let info = read_image_info(&path_buf, page, thumbnail_options.auto_orient)?;
+                    Ok((thumbnail, info))
+                })
+                .map_err(|e| e.to_string());
+            let _ = tx.send((first_id + idx, loaded));
+        });
+}
+
+// Re-render every loaded thumbnail with `options`, e.g. after switching preview resolution.
+// Items that were rotated or removed in the meantime keep whatever they have now.
+async fn refresh_thumbnails(images: Signal<Vec<ImageItem>>, options: ThumbnailOptions) {
+    refresh_thumbnails_until(images, options, std::sync::Arc::new(AtomicBool::new(false))).await;
+}
+
+// `refresh_thumbnails` that stops rendering once `cancelled` is set, for when a newer value has
+// made the result useless. Cancelling the task alone would leave the blocking work running.
+async fn refresh_thumbnails_until(
+    mut images: Signal<Vec<ImageItem>>,
+    options: ThumbnailOptions,
+    cancelled: std::sync::Arc<AtomicBool>,
+) {
+    let targets: Vec<(usize, PathBuf, Option<usize>, u16)> = images
+        .read()
+        .iter()
+        .map(|img| (img.id, img.path.clone(), img.page, img.rotation))
+        .collect();
+
+    let stop = cancelled.clone();
+    let Ok(thumbnails) = tokio::task::spawn_blocking(move || {
+        targets
+            .into_par_iter()
+            .filter_map(|(id, path, page, rotation)| {
+                if stop.load(Ordering::Relaxed) {
+                    return None;
+                }
+                create_thumbnail(&path, page, rotation, options)
+                    .ok()
+                    .map(|thumbnail| (id, rotation, thumbnail))
+            })
+            .collect::<Vec<_>>()
+    })
+    .await
+    else {
+        return;
+    };
+    if cancelled.load(Ordering::Relaxed) {
+        return;
+    }
+
+    let mut imgs = images.write();
+    for (id, rotation, thumbnail) in thumbnails {
+        if let Some(img) = imgs
+            .iter_mut()
+            .find(|img| img.id == id && img.rotation == rotation)
+        {
+            img.thumbnail_base64 = thumbnail;
+        }
+    }
+}
+
+// Re-render one item's thumbnail with its current rotation, leaving the rest of the list untouched.
+// The result is dropped if the item was rotated again or removed while it was generating.
+async fn refresh_thumbnail(
+    mut images: Signal<Vec<ImageItem>>,
+    id: usize,
+    options: ThumbnailOptions,
+) {
+    let Some((path, page, rotation)) = images
+        .read()
+        .iter()
+        .find(|img| img.id == id)
+        .map(|img| (img.path.clone(), img.page, img.rotation))
+    else {
+        return;
+    };
+
+    let result = tokio::task::spawn_blocking(move || {
+        create_thumbnail(&path, page, rotation, options).map_err(|e| e.to_string())
+    })
+    .await;
+    if let Ok(Ok(thumbnail)) = result {
+        if let Some(img) = images
+            .write()
+            .iter_mut()
+            .find(|img| img.id == id && img.rotation == rotation)
+        {
+            img.thumbnail_base64 = thumbnail;
         }
     }
 }
 
+// Check the first bytes of a file for a format we can decode, without reading the rest
+fn has_image_header(path: &Path) -> bool {
+    use std::io::Read;
+
+    #[cfg(feature = "heic")]
+    if heic::is_heif(path) {
+        return true;
+    }
+
+    let mut header = Vec::with_capacity(32);
+    let read = archive::open(path).and_then(|file| file.take(32).read_to_end(&mut header));
+    read.is_ok()
+        && image::guess_format(&header)
+            .ok()
+            .is_some_and(is_supported_format)
+}
+
+fn is_supported_format(format: ImageFormat) -> bool {
+    matches!(
+        format,
+        ImageFormat::Jpeg | ImageFormat::Png | ImageFormat::Tiff | ImageFormat::WebP
+    )
+}
+
+fn has_supported_extension(path: &Path) -> bool {
+    path.extension()
+        .map(|ext| ext.to_string_lossy().to_lowercase())
+        .is_some_and(|ext| SUPPORTED_EXTENSIONS.contains(&ext.as_str()))
+}
+
+// Record the current image order so it can be restored with Ctrl+Z
+fn push_history(mut history: Signal<Vec<Vec<ImageItem>>>, snapshot: Vec<ImageItem>) {
+    let mut stack = history.write();
+    stack.push(snapshot);
+    if stack.len() > UNDO_LIMIT {
+        stack.remove(0);
+    }
+}
+
+// Move an item `offset` positions earlier (negative) or later (positive), if there is room
+fn shift_item(
+    images: Signal<Vec<ImageItem>>,
+    history: Signal<Vec<Vec<ImageItem>>>,
+    item_id: usize,
+    offset: isize,
+) {
+    let Some(idx) = images.read().iter().position(|img| img.id == item_id) else {
+        return;
+    };
+    let target = idx as isize + offset;
+    if target >= 0 && (target as usize) < images.read().len() {
+        move_item(images, history, item_id, target as usize);
+    }
+}
+
+// Move every selected item to the front or back, keeping the relative order within both groups
+fn move_selection(
+    mut images: Signal<Vec<ImageItem>>,
+    history: Signal<Vec<Vec<ImageItem>>>,
+    selected: Signal<HashSet<usize>>,
+    to_front: bool,
+) {
+    let imgs = images.read().clone();
+    let (chosen, rest): (Vec<ImageItem>, Vec<ImageItem>) = imgs
+        .iter()
+        .cloned()
+        .partition(|img| selected.read().contains(&img.id));
+    if chosen.is_empty() {
+        return;
+    }
+
+    push_history(history, imgs);
+    images.set(if to_front {
+        chosen.into_iter().chain(rest).collect()
+    } else {
+        rest.into_iter().chain(chosen).collect()
+    });
+}
+
+// Take an item out of the list and insert it at `target_idx`, shifting the items in between
+fn move_item(
+    mut images: Signal<Vec<ImageItem>>,
+    history: Signal<Vec<Vec<ImageItem>>>,
+    item_id: usize,
+    target_idx: usize,
+) {
+    let mut imgs = images.read().clone();
+    let Some(idx) = imgs.iter().position(|img| img.id == item_id) else {
+        return;
+    };
+    let target_idx = target_idx.min(imgs.len() - 1);
+    if idx == target_idx {
+        return;
+    }
+
+    push_history(history, imgs.clone());
+    let item = imgs.remove(idx);
+    imgs.insert(target_idx, item);
+    images.set(imgs);
+}
+
 #[component]
 fn LoadingPopup() -> Element {
     rsx! {
@@ -104,119 +1148,677 @@ fn LoadingPopup() -> Element {
     }
 }
 
+// Post `message` to the OS notification centre, for runs finished while IRS is in the background.
+// Failing to show it is harmless, so errors are ignored.
+fn notify_system(message: String) {
+    spawn(async move {
+        let _ = tokio::task::spawn_blocking(move || {
+            notify_rust::Notification::new()
+                .appname("IRS")
+                .summary("IRS finished processing")
+                .body(&message)
+                .show()
+                .map(|_| ())
+        })
+        .await;
+    });
+}
+
+// Show a finished run's output in the OS file manager. Runs in the background, and a failure
+// only adds a notification since the files themselves were already written.
+fn reveal_folder(folder: PathBuf, notifications: Signal<Vec<Notification>>) {
+    spawn(async move {
+        let result = tokio::task::spawn_blocking(move || opener::open(&folder)).await;
+        if !matches!(result, Ok(Ok(()))) {
+            notify(
+                notifications,
+                "✗ Could not open the output folder".to_string(),
+                NotificationType::Error,
+            );
+        }
+    });
+}
+
+// How the lightbox image is zoomed and panned. The pan is the image centre's offset from the
+// centre of its frame in CSS pixels, applied before scaling around that centre.
+#[derive(Clone, Copy, Debug, PartialEq)]
+struct LightboxView {
+    zoom: f64,
+    pan_x: f64,
+    pan_y: f64,
+}
+
+impl LightboxView {
+    // The whole image, fitted to the window
+    const FIT: Self = Self {
+        zoom: 1.0,
+        pan_x: 0.0,
+        pan_y: 0.0,
+    };
+
+    // Zoom by `factor`, keeping the spot under the pointer in place. `x` and `y` are measured from
+    // the centre of the `width` × `height` frame.
+    fn zoomed(self, factor: f64, x: f64, y: f64, width: f64, height: f64) -> Self {
+        let zoom = (self.zoom * factor).clamp(1.0, MAX_LIGHTBOX_ZOOM);
+        let scale = zoom / self.zoom;
+        Self {
+            zoom,
+            pan_x: x - (x - self.pan_x) * scale,
+            pan_y: y - (y - self.pan_y) * scale,
+        }
+        .clamped(width, height)
+    }
+
+    fn panned(self, dx: f64, dy: f64, width: f64, height: f64) -> Self {
+        Self {
+            pan_x: self.pan_x + dx,
+            pan_y: self.pan_y + dy,
+            ..self
+        }
+        .clamped(width, height)
+    }
+
+    // Keep the image covering its frame, so no edge can be dragged inwards past the frame's
+    fn clamped(self, width: f64, height: f64) -> Self {
+        let max_x = (self.zoom - 1.0) * width / 2.0;
+        let max_y = (self.zoom - 1.0) * height / 2.0;
+        Self {
+            pan_x: self.pan_x.clamp(-max_x, max_x),
+            pan_y: self.pan_y.clamp(-max_y, max_y),
+            ..self
+        }
+    }
+}
+
+// The pieces one image becomes with the current settings, side by side, cut in memory without
+// writing anything. Esc or a click closes it.
 #[component]
-fn NotificationPopup(notification: Notification) -> Element {
-    let class_name = match notification.notification_type {
-        NotificationType::Info => "notification-info",
-        NotificationType::Success => "notification-success",
-        NotificationType::Error => "notification-error",
-        NotificationType::Processing => "notification-processing",
+fn SplitPreviewPopup(
+    images: Signal<Vec<ImageItem>>,
+    mut split_preview: Signal<Option<usize>>,
+    output: OutputOptions,
+) -> Element {
+    // Tagged with the item id, as in the lightbox, so a slow cut can't show up under another image
+    let mut pieces = use_signal(|| None::<(usize, Result<Vec<PreviewPiece>, String>)>);
+
+    let options = output.clone();
+    use_effect(move || {
+        let Some(item_id) = split_preview() else {
+            return;
+        };
+        // Numbering and page positions come from the image's place in the whole list
+        let imgs = images.peek().clone();
+        let options = options.clone();
+        spawn(async move {
+            let result = tokio::task::spawn_blocking(move || {
+                let idx = imgs
+                    .iter()
+                    .position(|img| img.id == item_id)
+                    .ok_or_else(|| "The image is no longer in the list".to_string())?;
+                let first_page = first_pages(&imgs, &options)[idx];
+                let (_, sequence_num) = output_slots(&imgs, &options).swap_remove(idx);
+                preview_split(&imgs[idx], sequence_num, first_page, &options)
+                    .map_err(|e| e.to_string())
+            })
+            .await
+            .unwrap_or_else(|e| Err(e.to_string()));
+            if split_preview() == Some(item_id) {
+                pieces.set(Some((item_id, result)));
+            }
+        });
+    });
+
+    let Some(item_id) = split_preview() else {
+        return rsx! {};
+    };
+    let Some(item) = images.read().iter().find(|img| img.id == item_id).cloned() else {
+        // The image was removed while open
+        return rsx! {};
     };
+    let name = item_name(&item);
+    let quadrants = item.split_mode.unwrap_or(output.split_mode) == SplitMode::Quadrants;
+    let current = pieces
+        .read()
+        .as_ref()
+        .filter(|(id, _)| *id == item_id)
+        .map(|(_, result)| result.clone());
 
     rsx! {
         div {
-            id: "notification-overlay",
-            div {
-                class: "notification-card {class_name}",
-                if notification.notification_type == NotificationType::Processing {
+            id: "split-preview-overlay",
+            tabindex: "0",
+            onmounted: move |evt: MountedEvent| async move {
+                let _ = evt.data().set_focus(true).await;
+            },
+            onclick: move |_| split_preview.set(None),
+            onkeydown: move |evt: KeyboardEvent| {
+                // Keep the keys away from the card list and the app shortcuts underneath
+                evt.stop_propagation();
+                if evt.key() == Key::Escape {
+                    evt.prevent_default();
+                    split_preview.set(None);
+                }
+            },
+            p {
+                class: "lightbox-caption",
+                "{name} — split with the current settings, nothing is written · Esc to close"
+            }
+            match current {
+                Some(Ok(pieces)) => rsx! {
+                    div {
+                        class: "split-preview-pieces",
+                        class: if quadrants { "quadrants" } else { "" },
+                        for piece in pieces {
+                            figure {
+                                key: "{piece.name}",
+                                class: "split-preview-piece",
+                                class: if !piece.written { "skipped" } else { "" },
+                                img {
+                                    src: "data:image/jpeg;base64,{piece.jpeg_base64}",
+                                    alt: "{piece.name}",
+                                }
+                                figcaption {
+                                    if piece.written {
+                                        "{piece.name} · {piece.width} × {piece.height}"
+                                    } else {
+                                        "{piece.name} · left out by the page filter"
+                                    }
+                                }
+                            }
+                        }
+                    }
+                },
+                Some(Err(err)) => rsx! {
+                    p {
+                        class: "lightbox-message",
+                        "✗ Could not split {name}: {err}"
+                    }
+                },
+                None => rsx! {
                     div {
                         class: "spinner",
                     }
-                }
-                p {
-                    "{notification.message}"
-                }
+                },
             }
         }
     }
 }
 
+// Full-resolution view of one image with arrow-key navigation through the list; Esc closes it.
+// The wheel zooms and dragging pans the decoded image in place; a double click fits it again.
 #[component]
-fn Controls(
+fn Lightbox(
     images: Signal<Vec<ImageItem>>,
-    folder_path: Signal<Option<PathBuf>>,
-    processing: Signal<bool>,
-    mut notification: Signal<Option<Notification>>,
-    mut loading_files: Signal<bool>,
+    mut lightbox: Signal<Option<usize>>,
+    auto_orient: bool,
+    background: [u8; 3],
 ) -> Element {
-    let mut show_notification = move |message: String, notification_type: NotificationType| {
-        let id = std::time::SystemTime::now()
-            .duration_since(std::time::UNIX_EPOCH)
-            .map(|d| d.as_millis() as u64)
-            .unwrap_or(0);
-        notification.set(Some(Notification {
-            message,
-            notification_type,
-            id,
-        }));
+    // Tagged with the item id so a slow decode can't show up under the image navigated to next
+    let mut full_image = use_signal(|| None::<(usize, Result<String, String>)>);
+    // EXIF summary as (label, value) rows, read from the file header only
+    let mut metadata = use_signal(|| None::<(usize, Vec<(&'static str, String)>)>);
 
-        if notification_type != NotificationType::Processing {
-            spawn(async move {
-                tokio::time::sleep(tokio::time::Duration::from_secs(3)).await;
-                notification.set(None);
-            });
-        }
+    // Zooming only changes a CSS transform, so the image isn't decoded again for each step.
+    // Pointer positions are measured against the frame around the image, which doesn't move.
+    let mut view = use_signal(|| LightboxView::FIT);
+    let mut image_frame = use_signal(|| None::<std::rc::Rc<MountedData>>);
+    // Last pointer position during a pan, with the frame size it's clamped to
+    let mut pan_drag = use_signal(|| None::<(f64, f64, dioxus::html::geometry::PixelsSize)>);
+    // Set once a pan has moved, so releasing the button over the backdrop doesn't close the view
+    let mut panned = use_signal(|| false);
+
+    use_effect(move || {
+        let Some(item_id) = lightbox() else {
+            return;
+        };
+        view.set(LightboxView::FIT);
+        let Some(item) = images.peek().iter().find(|img| img.id == item_id).cloned() else {
+            return;
+        };
+        let path = item.path.clone();
+        spawn(async move {
+            let rows = tokio::task::spawn_blocking(move || read_metadata(&path))
+                .await
+                .unwrap_or_default();
+            if lightbox() == Some(item_id) {
+                metadata.set(Some((item_id, rows)));
+            }
+        });
+        spawn(async move {
+            let result = tokio::task::spawn_blocking(move || {
+                create_full_preview(&item, auto_orient, background).map_err(|e| e.to_string())
+            })
+            .await
+            .unwrap_or_else(|e| Err(e.to_string()));
+            if lightbox() == Some(item_id) {
+                full_image.set(Some((item_id, result)));
+            }
+        });
+    });
+
+    let Some(item_id) = lightbox() else {
+        return rsx! {};
+    };
+    let imgs = images.read();
+    let Some(position) = imgs.iter().position(|img| img.id == item_id) else {
+        // The image was removed while open
+        return rsx! {};
+    };
+    let previous_id = position.checked_sub(1).map(|idx| imgs[idx].id);
+    let next_id = imgs.get(position + 1).map(|img| img.id);
+    let total = imgs.len();
+    let name = item_name(&imgs[position]);
+    drop(imgs);
+
+    let rows = metadata
+        .read()
+        .as_ref()
+        .filter(|(id, _)| *id == item_id)
+        .map(|(_, rows)| rows.clone());
+    let current = full_image
+        .read()
+        .as_ref()
+        .filter(|(id, _)| *id == item_id)
+        .map(|(_, result)| result.clone());
+
+    rsx! {
+        div {
+            id: "lightbox-overlay",
+            tabindex: "0",
+            onmounted: move |evt: MountedEvent| async move {
+                let _ = evt.data().set_focus(true).await;
+            },
+            onclick: move |_| {
+                if !panned() {
+                    lightbox.set(None);
+                }
+            },
+            onmousedown: move |_| panned.set(false),
+            onmousemove: move |evt: MouseEvent| {
+                let Some((x, y, size)) = pan_drag() else {
+                    return;
+                };
+                let point = evt.client_coordinates();
+                view.set(view().panned(point.x - x, point.y - y, size.width, size.height));
+                pan_drag.set(Some((point.x, point.y, size)));
+                panned.set(true);
+            },
+            onmouseup: move |_| pan_drag.set(None),
+            onkeydown: move |evt: KeyboardEvent| {
+                // Keep the keys away from the card list and the app shortcuts underneath
+                evt.stop_propagation();
+                match evt.key() {
+                    Key::Escape => lightbox.set(None),
+                    Key::ArrowLeft => {
+                        if let Some(id) = previous_id {
+                            lightbox.set(Some(id));
+                        }
+                    }
+                    Key::ArrowRight => {
+                        if let Some(id) = next_id {
+                            lightbox.set(Some(id));
+                        }
+                    }
+                    _ => return,
+                }
+                evt.prevent_default();
+            },
+            match current {
+                Some(Ok(data)) => rsx! {
+                    div {
+                        class: "lightbox-frame",
+                        onmounted: move |evt: MountedEvent| image_frame.set(Some(evt.data())),
+                        onwheel: move |evt: WheelEvent| {
+                            let delta = evt.delta().strip_units().y;
+                            let Some(frame) = image_frame() else {
+                                return;
+                            };
+                            if delta == 0.0 {
+                                return;
+                            }
+                            let factor = if delta < 0.0 {
+                                LIGHTBOX_ZOOM_STEP
+                            } else {
+                                1.0 / LIGHTBOX_ZOOM_STEP
+                            };
+                            let point = evt.client_coordinates();
+                            spawn(async move {
+                                if let Ok(rect) = frame.get_client_rect().await {
+                                    let size = rect.size;
+                                    let x = point.x - rect.origin.x - size.width / 2.0;
+                                    let y = point.y - rect.origin.y - size.height / 2.0;
+                                    view.set(view().zoomed(factor, x, y, size.width, size.height));
+                                }
+                            });
+                        },
+                        img {
+                            class: "lightbox-image",
+                            class: if view().zoom > 1.0 { "zoomed" } else { "" },
+                            class: if pan_drag().is_some() { "panning" } else { "" },
+                            style: "transform: translate({view().pan_x}px, {view().pan_y}px) scale({view().zoom});",
+                            src: "data:image/jpeg;base64,{data}",
+                            alt: "{name}",
+                            onclick: move |evt: MouseEvent| evt.stop_propagation(),
+                            ondoubleclick: move |evt: MouseEvent| {
+                                evt.stop_propagation();
+                                view.set(LightboxView::FIT);
+                            },
+                            onmousedown: move |evt: MouseEvent| {
+                                // Also keeps the webview from starting a drag of the image file
+                                evt.prevent_default();
+                                let Some(frame) = image_frame() else {
+                                    return;
+                                };
+                                if view().zoom <= 1.0 {
+                                    return;
+                                }
+                                let point = evt.client_coordinates();
+                                spawn(async move {
+                                    if let Ok(rect) = frame.get_client_rect().await {
+                                        pan_drag.set(Some((point.x, point.y, rect.size)));
+                                    }
+                                });
+                            },
+                        }
+                    }
+                },
+                Some(Err(err)) => rsx! {
+                    p {
+                        class: "lightbox-message",
+                        "✗ Could not load {name}: {err}"
+                    }
+                },
+                None => rsx! {
+                    div {
+                        class: "spinner",
+                    }
+                },
+            }
+            p {
+                class: "lightbox-caption",
+                "{name} — {position + 1} / {total} · ← → to browse, scroll to zoom, Esc to close"
+            }
+            if let Some(rows) = rows {
+                div {
+                    class: "lightbox-metadata",
+                    onclick: move |evt: MouseEvent| evt.stop_propagation(),
+                    if rows.is_empty() {
+                        span { "No metadata" }
+                    }
+                    for (label, value) in rows {
+                        span {
+                            strong { "{label}: " }
+                            "{value}"
+                        }
+                    }
+                }
+            }
+        }
+    }
+}
+
+// Collapsible per-image log of the last run, newest line last
+#[component]
+fn LogPanel(log: Signal<Vec<LogLine>>) -> Element {
+    if log.read().is_empty() {
+        return rsx! {};
+    }
+    let failures = log.read().iter().filter(|line| !line.success).count();
+
+    let copy_log = move |evt: MouseEvent| {
+        // The button sits in the summary, which would otherwise toggle the panel
+        evt.prevent_default();
+        let text = log
+            .read()
+            .iter()
+            .map(|line| line.text.as_str())
+            .collect::<Vec<_>>()
+            .join("\n");
+        // JSON string syntax is valid JavaScript, so this quotes the text safely
+        let quoted = serde_json::to_string(&text).unwrap_or_default();
+        document::eval(&format!("navigator.clipboard.writeText({})", quoted));
+    };
+
+    rsx! {
+        details {
+            id: "log-panel",
+            summary {
+                "LOG — {log.read().len()} lines, {failures} failed"
+                button {
+                    onclick: copy_log,
+                    "COPY"
+                }
+            }
+            div {
+                class: "log-lines",
+                for line in log() {
+                    p {
+                        class: if line.success { "log-success" } else { "log-error" },
+                        "{line.text}"
+                    }
+                }
+            }
+        }
+    }
+}
+
+// Lists the files a run would write, one line per source image
+#[component]
+fn DryRunPopup(plan: Vec<String>, mut dry_run_plan: Signal<Option<Vec<String>>>) -> Element {
+    rsx! {
+        div {
+            id: "dry-run-overlay",
+            div {
+                class: "dry-run-card",
+                p {
+                    class: "dry-run-title",
+                    "DRY RUN — nothing was written"
+                }
+                ul {
+                    class: "dry-run-list",
+                    for line in plan {
+                        li { "{line}" }
+                    }
+                }
+                button {
+                    onclick: move |_| dry_run_plan.set(None),
+                    "CLOSE"
+                }
+            }
+        }
+    }
+}
+
+// Asks before a destructive action; Cancel has focus so a stray Enter or Space does nothing harmful
+#[component]
+fn ConfirmPopup(
+    message: String,
+    confirm_label: &'static str,
+    on_confirm: EventHandler<()>,
+    on_cancel: EventHandler<()>,
+) -> Element {
+    rsx! {
+        div {
+            id: "confirm-overlay",
+            onkeydown: move |evt: KeyboardEvent| {
+                if evt.key() == Key::Escape {
+                    on_cancel.call(());
+                }
+            },
+            div {
+                class: "notification-card notification-error",
+                p { "{message}" }
+                div {
+                    class: "confirm-actions",
+                    button {
+                        onmounted: move |evt: MountedEvent| async move {
+                            let _ = evt.data().set_focus(true).await;
+                        },
+                        onclick: move |_| on_cancel.call(()),
+                        "CANCEL"
+                    }
+                    button {
+                        class: "confirm-button",
+                        onclick: move |_| on_confirm.call(()),
+                        "{confirm_label}"
+                    }
+                }
+            }
+        }
+    }
+}
+
+#[component]
+fn NotificationPopup(notification: Notification, progress: Option<(usize, usize)>) -> Element {
+    let class_name = match notification.notification_type {
+        NotificationType::Info => "notification-info",
+        NotificationType::Success => "notification-success",
+        NotificationType::Error => "notification-error",
+        NotificationType::Processing => "notification-processing",
+    };
+
+    rsx! {
+        div {
+            class: "notification-card {class_name}",
+            if notification.notification_type == NotificationType::Processing {
+                div {
+                    class: "spinner",
+                }
+            }
+            p {
+                "{notification.message}"
+            }
+            if notification.notification_type == NotificationType::Processing {
+                if let Some((done, total)) = progress {
+                    ProgressBar {
+                        done,
+                        total,
+                    }
+                }
+            }
+        }
+    }
+}
+
+#[component]
+fn ProgressBar(done: usize, total: usize) -> Element {
+    let percent = (done * 100).checked_div(total).unwrap_or(0);
+
+    rsx! {
+        div {
+            class: "progress",
+            div {
+                class: "progress-track",
+                div {
+                    class: "progress-fill",
+                    style: "width: {percent}%",
+                }
+            }
+            p {
+                class: "progress-label",
+                "Processing {done}/{total}"
+            }
+        }
+    }
+}
+
+#[component]
+fn Controls(
+    images: Signal<Vec<ImageItem>>,
+    folder_path: Signal<Option<PathBuf>>,
+    processing: Signal<bool>,
+    notifications: Signal<Vec<Notification>>,
+    mut loading_files: Signal<bool>,
+    mut settings: Signal<Settings>,
+    mut progress: Signal<Option<(usize, usize)>>,
+    history: Signal<Vec<Vec<ImageItem>>>,
+    mut selected: Signal<HashSet<usize>>,
+    mut dry_run_plan: Signal<Option<Vec<String>>>,
+    mut shortcut: Signal<Option<Shortcut>>,
+    mut log: Signal<Vec<LogLine>>,
+    mut filter: Signal<String>,
+) -> Element {
+    let mut dry_run = use_signal(|| false);
+    // Replaced with a fresh flag for every run so a late click can't cancel the next one
+    let mut cancel_flag = use_signal(|| std::sync::Arc::new(AtomicBool::new(false)));
+    // Failed images of the last run, if any, for RETRY FAILED
+    let mut retry = use_signal(|| None::<RetryRun>);
+    // Preview regeneration scheduled by the last slider step, with the flag that stops it
+    let mut live_refresh = use_signal(|| None::<(dioxus::core::Task, std::sync::Arc<AtomicBool>)>);
+    // The RECENT list and whether each batch is still there, checked when settings change rather
+    // than on every render
+    let recent_batches = use_memo(move || {
+        let recent = &settings.read().recent;
+        recent
+            .iter()
+            .map(|batch| (batch.clone(), batch.exists()))
+            .collect::<Vec<_>>()
+    });
+    // Regenerate the previews once a slider has rested for LIVE_PREVIEW_DELAY_MS, dropping any
+    // regeneration still waiting or running for an earlier value
+    let mut refresh_live = move || {
+        if let Some((task, cancelled)) = live_refresh.write().take() {
+            task.cancel();
+            cancelled.store(true, Ordering::Relaxed);
+        }
+        let cancelled = std::sync::Arc::new(AtomicBool::new(false));
+        let task = spawn({
+            let cancelled = cancelled.clone();
+            async move {
+                tokio::time::sleep(tokio::time::Duration::from_millis(LIVE_PREVIEW_DELAY_MS)).await;
+                let options = settings.read().thumbnail_options();
+                refresh_thumbnails_until(images, options, cancelled).await;
+            }
+        });
+        live_refresh.set(Some((task, cancelled)));
+    };
+    let show_notification = move |message: String, notification_type: NotificationType| {
+        notify(notifications, message, notification_type);
     };
 
-    let open_files = move |_| {
+    // Pick files and replace the loaded set with them, or add them at `placement`
+    let mut pick_files = move |placement: Placement| {
         loading_files.set(true);
 
         spawn({
             async move {
                 match rfd::AsyncFileDialog::new()
-                    .add_filter("jpg", &["jpg", "jpeg"])
+                    .add_filter("images", SUPPORTED_EXTENSIONS)
                     .pick_files()
                     .await
                 {
                     Some(paths) if !paths.is_empty() => {
-                        let folder = paths[0]
-                            .path()
-                            .parent()
-                            .map(|p| p.to_path_buf())
-                            .unwrap_or_else(|| PathBuf::from("."));
-                        folder_path.set(Some(folder));
+                        if placement == Placement::Replace {
+                            // Replacing the set reuses ids from 0, so old selections would point at new images
+                            selected.write().clear();
+                        }
+                        if placement == Placement::Replace || folder_path().is_none() {
+                            let folder = paths[0]
+                                .path()
+                                .parent()
+                                .map(|p| p.to_path_buf())
+                                .unwrap_or_else(|| PathBuf::from("."));
+                            folder_path.set(Some(folder));
+                        }
 
                         let file_paths: Vec<PathBuf> =
                             paths.iter().map(|p| p.path().to_path_buf()).collect();
+                        if placement == Placement::Replace {
+                            settings
+                                .write()
+                                .remember(RecentBatch::Files(file_paths.clone()));
+                        }
 
-                        let total_files = file_paths.len();
-
-                        tokio::task::spawn_blocking(move || {
-                            let mut image_items = Vec::new();
-                            let mut id = 0;
-
-                            for path_buf in file_paths {
-                                match create_thumbnail(&path_buf) {
-                                    Ok(thumbnail_base64) => {
-                                        image_items.push(ImageItem {
-                                            id,
-                                            path: path_buf,
-                                            thumbnail_base64,
-                                        });
-                                        id += 1;
-                                    }
-                                    Err(_) => {}
-                                }
-                            }
-
-                            (image_items, total_files)
-                        })
-                        .await
-                        .ok()
-                        .map(|(image_items, _)| {
-                            if !image_items.is_empty() {
-                                images.set(image_items.clone());
-                                show_notification(
-                                    format!("✓ Loaded {} images", image_items.len()),
-                                    NotificationType::Success,
-                                );
-                            } else {
-                                show_notification(
-                                    "✗ No valid images found".to_string(),
-                                    NotificationType::Error,
-                                );
-                            }
-                        });
+                        let thumbnail_options = settings.read().thumbnail_options();
+                        load_images(
+                            file_paths,
+                            images,
+                            notifications,
+                            log,
+                            placement,
+                            thumbnail_options,
+                        )
+                        .await;
                     }
                     _ => {
                         show_notification("No files selected".to_string(), NotificationType::Info);
@@ -227,515 +1829,6459 @@ fn Controls(
         });
     };
 
-    let clear_images = move |_| {
-        images.set(Vec::new());
-        folder_path.set(None);
-        show_notification("Cleared all images".to_string(), NotificationType::Info);
-    };
+    let open_files = move |_| pick_files(Placement::Replace);
+    let add_files = move |_| pick_files(Placement::Append);
 
-    let rename_split = move |_| {
-        if images().is_empty() {
-            show_notification("No images to process".to_string(), NotificationType::Error);
-            return;
+    // Fill a gap in the sequence: new files go right after the last selected image
+    let insert_files = move |_| {
+        let after = images
+            .read()
+            .iter()
+            .rposition(|img| selected.read().contains(&img.id));
+        if let Some(idx) = after {
+            pick_files(Placement::InsertAt(idx + 1));
         }
+    };
 
-        processing.set(true);
-        show_notification(
-            "Selecting save location...".to_string(),
-            NotificationType::Info,
-        );
-
-        let imgs = images.read().clone();
+    // Replace the loaded set with every image found in a folder
+    let open_folder = move |_| {
+        loading_files.set(true);
+        let recursive = settings.read().recursive_scan;
+        let thumbnail_options = settings.read().thumbnail_options();
 
-        spawn({
-            async move {
-                match rfd::AsyncFileDialog::new()
-                    .set_title("Select folder to save split images")
-                    .pick_folder()
-                    .await
-                {
-                    Some(folder_handle) => {
-                        let save_folder = folder_handle.path().to_path_buf();
+        spawn(async move {
+            match rfd::AsyncFileDialog::new()
+                .set_title("Select a folder of images")
+                .pick_folder()
+                .await
+            {
+                Some(folder_handle) => {
+                    let folder = folder_handle.path().to_path_buf();
+                    let root = folder.clone();
+                    let file_paths =
+                        tokio::task::spawn_blocking(move || scan::collect_images(&root, recursive))
+                            .await
+                            .unwrap_or_default();
 
-                        // Notify user that processing is starting (processing popup)
+                    if file_paths.is_empty() {
                         show_notification(
-                            "Processing images...".to_string(),
-                            NotificationType::Processing,
+                            "✗ No supported images in that folder".to_string(),
+                            NotificationType::Error,
                         );
+                    } else {
+                        selected.write().clear();
+                        settings.write().remember(RecentBatch::Folder {
+                            path: folder.clone(),
+                            recursive,
+                        });
+                        folder_path.set(Some(folder));
+                        load_images(
+                            file_paths,
+                            images,
+                            notifications,
+                            log,
+                            Placement::Replace,
+                            thumbnail_options,
+                        )
+                        .await;
+                    }
+                }
+                None => {
+                    show_notification("No folder selected".to_string(), NotificationType::Info);
+                }
+            }
+            loading_files.set(false);
+        });
+    };
 
-                        // Run CPU-bound processing on a blocking thread but await it here so we can update UI safely.
-                        // This prevents the UI from freezing while still allowing us to set notifications after completion.
-                        let imgs_for_bg = imgs.clone();
-                        match tokio::task::spawn_blocking(move || {
-                            process_images_sync(imgs_for_bg, save_folder)
-                        })
-                        .await
-                        {
-                            Ok(Ok(processed_count)) => {
-                                show_notification(
-                                    format!("✓ Completed! Processed {} images", processed_count),
-                                    NotificationType::Success,
-                                );
-                            }
-                            Ok(Err(err_msg)) => {
-                                show_notification(
-                                    format!("✗ Processing error: {}", err_msg),
-                                    NotificationType::Error,
-                                );
-                            }
-                            Err(join_err) => {
-                                show_notification(
-                                    format!("✗ Processing task failed: {}", join_err),
-                                    NotificationType::Error,
-                                );
-                            }
-                        }
+    // Replace the loaded set with the images in a ZIP archive, read straight from it
+    let open_archive = move |_| {
+        loading_files.set(true);
+        let thumbnail_options = settings.read().thumbnail_options();
 
-                        // Ensure processing flag is cleared
-                        processing.set(false);
-                    }
-                    None => {
-                        show_notification(
-                            "Save location cancelled".to_string(),
-                            NotificationType::Info,
-                        );
-                        processing.set(false);
+        spawn(async move {
+            match rfd::AsyncFileDialog::new()
+                .set_title("Select a ZIP archive of images")
+                .add_filter("ZIP archive", &[archive::ARCHIVE_EXTENSION])
+                .pick_file()
+                .await
+            {
+                Some(file) => {
+                    let archive_path = file.path().to_path_buf();
+                    let path = archive_path.clone();
+                    let listed = tokio::task::spawn_blocking(move || {
+                        archive::image_paths(&path).map_err(|e| e.to_string())
+                    })
+                    .await
+                    .unwrap_or_else(|e| Err(e.to_string()));
+
+                    match listed {
+                        Ok(file_paths) if !file_paths.is_empty() => {
+                            selected.write().clear();
+                            // Output goes next to the archive, as it would for a folder of files
+                            folder_path.set(archive_path.parent().map(Path::to_path_buf));
+                            settings
+                                .write()
+                                .remember(RecentBatch::Archive(archive_path.clone()));
+                            load_images(
+                                file_paths,
+                                images,
+                                notifications,
+                                log,
+                                Placement::Replace,
+                                thumbnail_options,
+                            )
+                            .await;
+                        }
+                        Ok(_) => show_notification(
+                            "✗ No supported images in that archive".to_string(),
+                            NotificationType::Error,
+                        ),
+                        Err(e) => show_notification(
+                            format!("✗ Could not read the archive: {}", e),
+                            NotificationType::Error,
+                        ),
                     }
                 }
+                None => {
+                    show_notification("No archive selected".to_string(), NotificationType::Info);
+                }
             }
+            loading_files.set(false);
         });
     };
 
-    rsx! {
-        div {
-            id: "controls",
-            button {
-                id: "open-button",
-                onclick: open_files,
-                disabled: processing() || loading_files(),
-                "OPEN"
-            }
-            button {
-                id: "clear-button",
-                onclick: clear_images,
-                disabled: processing() || loading_files(),
-                "CLEAR"
+    // Open a batch from the RECENT list again in place of the current set
+    let reopen_recent = move |evt: FormEvent| {
+        let Some(batch) = evt
+            .value()
+            .parse::<usize>()
+            .ok()
+            .and_then(|idx| settings.read().recent.get(idx).cloned())
+        else {
+            return;
+        };
+        loading_files.set(true);
+        let thumbnail_options = settings.read().thumbnail_options();
+
+        spawn(async move {
+            let listed = tokio::task::spawn_blocking({
+                let batch = batch.clone();
+                move || batch.image_paths()
+            })
+            .await
+            .unwrap_or_else(|e| Err(e.to_string()));
+
+            match listed {
+                Ok(file_paths) if !file_paths.is_empty() => {
+                    selected.write().clear();
+                    folder_path.set(Some(batch.save_folder()));
+                    settings.write().remember(batch);
+                    load_images(
+                        file_paths,
+                        images,
+                        notifications,
+                        log,
+                        Placement::Replace,
+                        thumbnail_options,
+                    )
+                    .await;
+                }
+                Ok(_) => show_notification(
+                    format!("✗ No supported images left in {}", batch.label()),
+                    NotificationType::Error,
+                ),
+                Err(e) => show_notification(
+                    format!("✗ Could not open {}: {}", batch.label(), e),
+                    NotificationType::Error,
+                ),
             }
-            button {
-                id: "rename-split-button",
-                onclick: rename_split,
-                disabled: processing() || loading_files(),
-                "RENAME & SPLIT"
+            loading_files.set(false);
+        });
+    };
+
+    // Save the current order, per-image rotation and split ratio, and output settings
+    let save_project = move |_| {
+        let project = project::Project::new(&images.read(), &settings.read().output);
+        spawn(async move {
+            let Some(file) = rfd::AsyncFileDialog::new()
+                .set_title("Save project")
+                .add_filter("IRS project", &[project::PROJECT_EXTENSION])
+                .set_file_name(format!("project.{}", project::PROJECT_EXTENSION))
+                .save_file()
+                .await
+            else {
+                return;
+            };
+            match project.save(file.path()) {
+                Ok(()) => show_notification(
+                    format!("✓ Saved project with {} images", project.items.len()),
+                    NotificationType::Success,
+                ),
+                Err(e) => show_notification(
+                    format!("✗ Could not save project: {}", e),
+                    NotificationType::Error,
+                ),
             }
-        }
-    }
-}
+        });
+    };
+
+    // Replace the loaded set with a saved project. Files that have gone missing are skipped and named.
+    let open_project = move |_| {
+        loading_files.set(true);
+
+        spawn(async move {
+            let Some(file) = rfd::AsyncFileDialog::new()
+                .set_title("Open project")
+                .add_filter("IRS project", &[project::PROJECT_EXTENSION])
+                .pick_file()
+                .await
+            else {
+                loading_files.set(false);
+                return;
+            };
+            let project = match project::Project::load(file.path()) {
+                Ok(project) => project,
+                Err(e) => {
+                    show_notification(
+                        format!("✗ Could not open project: {}", e),
+                        NotificationType::Error,
+                    );
+                    loading_files.set(false);
+                    return;
+                }
+            };
+
+            let (present, missing): (Vec<_>, Vec<_>) = project
+                .items
+                .into_iter()
+                .partition(|item| archive::exists(&item.path));
+            if !missing.is_empty() {
+                let names: Vec<String> = missing
+                    .iter()
+                    .map(|item| item.path.display().to_string())
+                    .collect();
+                let message = if missing.len() == 1 {
+                    format!(
+                        "✗ 1 file in the project is missing: {}",
+                        summarize_names(&names)
+                    )
+                } else {
+                    format!(
+                        "✗ {} files in the project are missing: {}",
+                        missing.len(),
+                        summarize_names(&names)
+                    )
+                };
+                show_notification(message, NotificationType::Error);
+            }
+            if present.is_empty() {
+                loading_files.set(false);
+                return;
+            }
+
+            // Previews follow the project's orientation and tone settings
+            settings.write().output = project.output;
+            let thumbnail_options = settings.read().thumbnail_options();
+            selected.write().clear();
+            folder_path.set(present[0].path.parent().map(Path::to_path_buf));
+
+            let sources = present
+                .iter()
+                .map(|item| (item.path.clone(), item.page))
+                .collect();
+            load_pages(
+                sources,
+                images,
+                notifications,
+                log,
+                Placement::Replace,
+                thumbnail_options,
+            )
+            .await;
+
+            // Items come back in project order, but unreadable files may have dropped out in between,
+            // so match them up by path and page. A page listed twice takes its saved values in order.
+            let mut saved: HashMap<_, std::collections::VecDeque<project::ProjectItem>> =
+                HashMap::new();
+            for item in present {
+                saved
+                    .entry((item.path.clone(), item.page))
+                    .or_default()
+                    .push_back(item);
+            }
+            let mut any_rotated = false;
+            for img in images.write().iter_mut() {
+                let key = (img.path.clone(), img.page);
+                if let Some(item) = saved.get_mut(&key).and_then(|items| items.pop_front()) {
+                    img.rotation = item.rotation;
+                    img.split_ratio = item.split_ratio;
+                    img.split_mode = item.split_mode;
+                    img.quality = item.quality;
+                    img.dpi = item.dpi;
+                    any_rotated |= item.rotation != 0;
+                }
+            }
+            if any_rotated {
+                // Unrotated previews were just cached, so only the rotated ones cost a decode
+                refresh_thumbnails(images, thumbnail_options).await;
+            }
+            loading_files.set(false);
+        });
+    };
+
+    let mut clear_images = move || {
+        if !images.read().is_empty() {
+            push_history(history, images.read().clone());
+        }
+        images.set(Vec::new());
+        selected.write().clear();
+        retry.set(None);
+        folder_path.set(None);
+        show_notification("Cleared all images".to_string(), NotificationType::Info);
+    };
+
+    // Clearing an arranged set is a big loss even with undo, so it's confirmed first unless empty
+    let mut confirm_clear = use_signal(|| false);
+    // Set when RENAME & SPLIT finds sources that have gone missing, offering to drop them
+    let mut missing_sources = use_signal(|| None::<MissingSources>);
+    let mut request_clear = move || {
+        if images.read().is_empty() {
+            clear_images();
+        } else {
+            confirm_clear.set(true);
+        }
+    };
+
+    // Process `imgs` (only the ids in `only`, if given) into `save_folder` and report the outcome.
+    // Failures are kept so RETRY FAILED can redo just those images.
+    let run_processing = move |imgs: Vec<ImageItem>,
+                               only: Option<HashSet<usize>>,
+                               save_folder: PathBuf,
+                               options: OutputOptions,
+                               cancel: std::sync::Arc<AtomicBool>| async move {
+        // Notify user that processing is starting (processing popup). Other messages shown during
+        // the run leave it be; it's taken down once the run is over.
+        let processing_id = notify(
+            notifications,
+            "Processing images...".to_string(),
+            NotificationType::Processing,
+        );
+        let open_output = settings.read().open_output;
+        let notify_when_done = settings.read().notify_when_done;
+        let continue_numbering = settings.read().continue_numbering && only.is_none();
+        let total = only.as_ref().map_or(imgs.len(), |ids| ids.len());
+
+        // Run CPU-bound processing on a blocking thread but await it here so we can update UI safely.
+        // This prevents the UI from freezing while still allowing us to set notifications after completion.
+        let imgs_for_bg = imgs.clone();
+        let options_for_bg = options.clone();
+
+        // Worker threads report (done, total) and each image's log line through this channel;
+        // a UI task mirrors them into `progress` and `log`
+        let (progress_tx, mut progress_rx) =
+            tokio::sync::mpsc::unbounded_channel::<(usize, usize, LogLine)>();
+        progress.set(Some((0, total)));
+        log.write().clear();
+        spawn(async move {
+            while let Some((done, total, line)) = progress_rx.recv().await {
+                // Updates still queued when the run finishes mustn't bring the progress (and title) back
+                if processing() {
+                    progress.set(Some((done, total)));
+                }
+                let mut lines = log.write();
+                lines.push(line);
+                if lines.len() > MAX_LOG_LINES {
+                    let excess = lines.len() - MAX_LOG_LINES;
+                    lines.drain(..excess);
+                }
+            }
+        });
+
+        let result = tokio::task::spawn_blocking(move || {
+            process_images_sync(
+                imgs_for_bg,
+                only,
+                save_folder,
+                options_for_bg,
+                cancel,
+                |done, total, line| {
+                    let _ = progress_tx.send((done, total, line));
+                },
+            )
+        })
+        .await;
+        notifications
+            .write()
+            .retain(|notification| notification.id != processing_id);
+        match result {
+            Ok(Ok(summary)) => {
+                let mut message = if summary.cancelled {
+                    format!(
+                        "Cancelled after processing {} of {} images",
+                        summary.processed, total
+                    )
+                } else {
+                    format!("✓ Completed! Processed {} images", summary.processed)
+                };
+                if summary.stats.skipped > 0 {
+                    message.push_str(&format!(
+                        ", skipped {} existing files",
+                        summary.stats.skipped
+                    ));
+                }
+                if summary.stats.renamed > 0 {
+                    message.push_str(&format!(
+                        ", renamed {} to avoid overwriting",
+                        summary.stats.renamed
+                    ));
+                }
+                if let Some(pdf_name) = &summary.pdf {
+                    message.push_str(&format!(", saved {}", pdf_name));
+                }
+                if open_output && summary.processed > 0 {
+                    // The folder an archive was saved in, rather than opening the archive
+                    let shown = if options.zip_output {
+                        summary.folder.parent().map(Path::to_path_buf)
+                    } else {
+                        Some(summary.folder.clone())
+                    };
+                    if let Some(folder) = shown {
+                        reveal_folder(folder, notifications);
+                    }
+                }
+                // Continue after every number this run handed out, including any a cancel left unused
+                if continue_numbering && summary.processed > 0 {
+                    settings.write().output.start_index = options.start_index + imgs.len();
+                }
+
+                // A cancel with no new failures leaves the images still waiting for a retry alone.
+                // Failed pages can't be patched into a PDF or a finished ZIP, so those runs offer
+                // no retry.
+                if !summary.failed_ids.is_empty()
+                    && options.pdf_export != PdfExport::Only
+                    && !options.zip_output
+                {
+                    let save_folder = summary.folder.parent().map(Path::to_path_buf);
+                    let subfolder = summary.folder.file_name();
+                    if let (Some(save_folder), Some(subfolder)) = (save_folder, subfolder) {
+                        retry.set(Some(RetryRun {
+                            // Only the order and sizes matter for numbering, not the previews
+                            images: imgs
+                                .iter()
+                                .map(|img| ImageItem {
+                                    thumbnail_base64: String::new(),
+                                    ..img.clone()
+                                })
+                                .collect(),
+                            ids: summary.failed_ids.iter().copied().collect(),
+                            save_folder,
+                            options: OutputOptions {
+                                // Back into the very folder of this run, timestamp and all
+                                subfolder: subfolder.to_string_lossy().to_string(),
+                                timestamp_subfolder: false,
+                                // A failed image's own files are incomplete, so they're replaced
+                                overwrite_policy: OverwritePolicy::Overwrite,
+                                // A PDF of just the retried pages would be misleading
+                                pdf_export: PdfExport::Off,
+                                ..options
+                            },
+                        }));
+                    }
+                } else if !summary.cancelled || !summary.failed_ids.is_empty() {
+                    retry.set(None);
+                }
+
+                if summary.failed.is_empty() {
+                    let notification_type = if summary.cancelled {
+                        NotificationType::Info
+                    } else {
+                        NotificationType::Success
+                    };
+                    if notify_when_done {
+                        notify_system(message.clone());
+                    }
+                    show_notification(message, notification_type);
+                } else {
+                    let message = format!(
+                        "✗ {} of {} images failed: {}",
+                        summary.failed.len(),
+                        summary.processed + summary.failed.len(),
+                        summarize_names(&summary.failed)
+                    );
+                    if notify_when_done {
+                        notify_system(format!(
+                            "Processed {} images. {}",
+                            summary.processed,
+                            message.trim_start_matches("✗ ")
+                        ));
+                    }
+                    show_notification(message, NotificationType::Error);
+                }
+            }
+            Ok(Err(err)) => {
+                let message = processing_error_message(&err);
+                if notify_when_done {
+                    notify_system(message.clone());
+                }
+                show_notification(message, NotificationType::Error);
+            }
+            Err(join_err) => {
+                let message = format!("✗ Processing task failed: {}", join_err);
+                if notify_when_done {
+                    notify_system(message.clone());
+                }
+                show_notification(message, NotificationType::Error);
+            }
+        }
+
+        // Ensure processing flag is cleared
+        processing.set(false);
+        progress.set(None);
+    };
+
+    // Redo only the images that failed last time, into the same folder with the same numbers
+    let retry_failed = move |_| {
+        let Some(run) = retry() else {
+            return;
+        };
+        processing.set(true);
+        let cancel = std::sync::Arc::new(AtomicBool::new(false));
+        cancel_flag.set(std::sync::Arc::clone(&cancel));
+        spawn(run_processing(
+            run.images,
+            Some(run.ids),
+            run.save_folder,
+            run.options,
+            cancel,
+        ));
+    };
+
+    let mut rename_split = move || {
+        if images().is_empty() {
+            show_notification("No images to process".to_string(), NotificationType::Error);
+            return;
+        }
+
+        let imgs = images.read().clone();
+        let options = settings.read().output.clone();
+        if let Err(err_msg) = validate_subfolder_name(&options.subfolder)
+            .and_then(|_| validate_name_template(&options.name_template))
+        {
+            show_notification(format!("✗ {}", err_msg), NotificationType::Error);
+            return;
+        }
+        if let Some(missing) = find_missing_sources(&imgs, None) {
+            missing_sources.set(Some(missing));
+            return;
+        }
+        // A destination that has since been deleted or unmounted falls back to the dialog
+        let last_folder = settings
+            .read()
+            .last_save_folder
+            .clone()
+            .filter(|folder| folder.is_dir());
+        let reuse_folder = settings.read().reuse_save_folder;
+
+        processing.set(true);
+        show_notification(
+            "Selecting save location...".to_string(),
+            NotificationType::Info,
+        );
+        let cancel = std::sync::Arc::new(AtomicBool::new(false));
+        cancel_flag.set(std::sync::Arc::clone(&cancel));
+
+        spawn({
+            async move {
+                let chosen_folder = match last_folder {
+                    Some(folder) if reuse_folder => Some(folder),
+                    last_folder => {
+                        let mut dialog = rfd::AsyncFileDialog::new()
+                            .set_title("Select folder to save split images");
+                        if let Some(folder) = last_folder {
+                            dialog = dialog.set_directory(folder);
+                        }
+                        dialog
+                            .pick_folder()
+                            .await
+                            .map(|handle| handle.path().to_path_buf())
+                    }
+                };
+                if let Some(folder) = &chosen_folder {
+                    if settings.read().last_save_folder.as_ref() != Some(folder) {
+                        settings.write().last_save_folder = Some(folder.clone());
+                    }
+                }
+
+                match chosen_folder {
+                    Some(save_folder) if dry_run() => {
+                        // Plan against the chosen folder so existing files show up as skipped or renamed
+                        let spl_folder = save_folder.join(output_subfolder_name(&options));
+                        match tokio::task::spawn_blocking(move || {
+                            plan_output_names(&imgs, &spl_folder, &options)
+                        })
+                        .await
+                        {
+                            Ok(plan) => {
+                                notifications.write().clear();
+                                dry_run_plan.set(Some(plan));
+                            }
+                            Err(join_err) => {
+                                show_notification(
+                                    format!("✗ Dry run failed: {}", join_err),
+                                    NotificationType::Error,
+                                );
+                            }
+                        }
+                        processing.set(false);
+                    }
+                    Some(save_folder) => {
+                        run_processing(imgs, None, save_folder, options, cancel).await;
+                    }
+                    None => {
+                        show_notification(
+                            "Save location cancelled".to_string(),
+                            NotificationType::Info,
+                        );
+                        processing.set(false);
+                    }
+                }
+            }
+        });
+    };
+
+    // Run a shortcut from App's key handler, ignoring it while the buttons would be disabled
+    use_effect(move || {
+        let Some(action) = shortcut() else {
+            return;
+        };
+        shortcut.set(None);
+        if *processing.peek() || *loading_files.peek() {
+            return;
+        }
+        match action {
+            Shortcut::Open => pick_files(Placement::Replace),
+            Shortcut::Clear => request_clear(),
+            Shortcut::RenameSplit => rename_split(),
+        }
+    });
+
+    let remove_selected = move |_| {
+        let imgs = images.read().clone();
+        push_history(history, imgs.clone());
+        images.set(
+            imgs.into_iter()
+                .filter(|img| !selected.read().contains(&img.id))
+                .collect(),
+        );
+        selected.write().clear();
+    };
+
+    let mut sort_images = move |order: SortOrder| {
+        let imgs = images.read().clone();
+        let sorted = sorted_images(&imgs, order);
+        if sorted != imgs {
+            push_history(history, imgs);
+            images.set(sorted);
+        }
+    };
+
+    let move_selected_to_front = move |_| move_selection(images, history, selected, true);
+    let move_selected_to_back = move |_| move_selection(images, history, selected, false);
+
+    let output = settings.read().output.clone();
+    // Quality only matters when something is JPEG-encoded, which includes PDF pages
+    let uses_jpeg = output.format == OutputFormat::Jpeg || output.pdf_export != PdfExport::Off;
+    // Comments go into JPEG files only, not into PDF pages
+    let writes_jpeg_files =
+        output.format == OutputFormat::Jpeg && output.pdf_export != PdfExport::Only;
+    // Only a cut into two pieces has halves to flip
+    let has_halves =
+        !output.rename_only && output.split_mode != SplitMode::Quadrants && output.splits == 2;
+    let recent = recent_batches();
+    let card_size = settings.read().card_size;
+    let view_mode = settings.read().view_mode;
+    let hires_previews = settings.read().hires_previews;
+    let preview_fit = settings.read().preview_fit;
+    let recursive_scan = settings.read().recursive_scan;
+    let open_output = settings.read().open_output;
+    let notify_when_done = settings.read().notify_when_done;
+    let continue_numbering = settings.read().continue_numbering;
+    let reuse_save_folder = settings.read().reuse_save_folder;
+    let reuse_folder_hint = match &settings.read().last_save_folder {
+        Some(folder) => format!("Save to {} without asking", folder.display()),
+        None => "Available after the first save".to_string(),
+    };
+    let has_last_folder = settings.read().last_save_folder.is_some();
+    let thumbnail_quality = settings.read().thumbnail_quality;
+    let large_image_megapixels = settings.read().large_image_megapixels;
+    // Undo can bring back a list that doesn't contain every selected id, so count what's actually present
+    let selected_count = images
+        .read()
+        .iter()
+        .filter(|img| selected.read().contains(&img.id))
+        .count();
+    let image_count = images.read().len();
+    // Placeholders still loading count as 0 bytes until their file details arrive
+    let total_size = format_file_size(images.read().iter().map(|img| img.file_size).sum());
 
-#[component]
-fn ImagePreview(
-    images: Signal<Vec<ImageItem>>,
-    drag_source: Signal<Option<usize>>,
-    drag_over_id: Signal<Option<usize>>,
-) -> Element {
     rsx! {
+        if confirm_clear() {
+            ConfirmPopup {
+                message: format!("Remove all {} images from the list?", image_count),
+                confirm_label: "CLEAR",
+                on_confirm: move |_| {
+                    confirm_clear.set(false);
+                    clear_images();
+                },
+                on_cancel: move |_| confirm_clear.set(false),
+            }
+        }
+        if let Some(missing) = missing_sources.read().as_ref() {
+            ConfirmPopup {
+                message: format!("{}. Remove them from the list?", missing.message()),
+                confirm_label: "REMOVE",
+                on_confirm: move |_| {
+                    let Some(missing) = missing_sources.take() else {
+                        return;
+                    };
+                    let imgs = images.read().clone();
+                    push_history(history, imgs.clone());
+                    images.set(
+                        imgs.into_iter()
+                            .filter(|img| !missing.ids.contains(&img.id))
+                            .collect(),
+                    );
+                    selected.write().retain(|id| !missing.ids.contains(id));
+                    show_notification(
+                        if missing.ids.len() == 1 {
+                            "Removed 1 missing image".to_string()
+                        } else {
+                            format!("Removed {} missing images", missing.ids.len())
+                        },
+                        NotificationType::Info,
+                    );
+                },
+                on_cancel: move |_| missing_sources.set(None),
+            }
+        }
         div {
-            id: "image-preview",
-            if images().is_empty() {
+            id: "controls",
+            button {
+                id: "open-button",
+                title: "Open images (Ctrl+O)",
+                onclick: open_files,
+                disabled: processing() || loading_files(),
+                "OPEN"
+            }
+            button {
+                id: "open-folder-button",
+                title: "Open every image in a folder",
+                onclick: open_folder,
+                disabled: processing() || loading_files(),
+                "FOLDER"
+            }
+            button {
+                id: "open-archive-button",
+                title: "Open the images in a ZIP archive without extracting it",
+                onclick: open_archive,
+                disabled: processing() || loading_files(),
+                "ZIP"
+            }
+            div {
+                class: "setting",
+                title: "Open a batch again; ones that are no longer there are greyed out",
+                label {
+                    r#for: "recent-batches",
+                    "RECENT"
+                }
+                select {
+                    id: "recent-batches",
+                    disabled: processing() || loading_files() || recent.is_empty(),
+                    onchange: reopen_recent,
+                    option {
+                        value: "",
+                        selected: true,
+                        if recent.is_empty() {
+                            "NONE"
+                        } else {
+                            "OPEN AGAIN…"
+                        }
+                    }
+                    for (idx, (batch, available)) in recent.iter().enumerate() {
+                        option {
+                            key: "{idx}",
+                            value: "{idx}",
+                            title: batch.location(),
+                            disabled: !available,
+                            "{batch.label()}"
+                        }
+                    }
+                }
+            }
+            button {
+                id: "add-button",
+                onclick: add_files,
+                disabled: processing() || loading_files(),
+                "ADD"
+            }
+            button {
+                id: "clear-button",
+                title: "Clear all images (Ctrl+Shift+C)",
+                onclick: move |_| request_clear(),
+                disabled: processing() || loading_files(),
+                "CLEAR"
+            }
+            button {
+                class: "sort-button",
+                title: "Save the order, rotations, split positions and settings to a file",
+                onclick: save_project,
+                disabled: processing() || loading_files() || images.read().is_empty(),
+                "SAVE PROJECT"
+            }
+            button {
+                class: "sort-button",
+                title: "Restore a saved project",
+                onclick: open_project,
+                disabled: processing() || loading_files(),
+                "OPEN PROJECT"
+            }
+            button {
+                id: "rename-split-button",
+                title: "Rename and split (Ctrl+Enter)",
+                onclick: move |_| rename_split(),
+                disabled: processing() || loading_files(),
+                "RENAME & SPLIT"
+            }
+            if let Some(run) = retry() {
+                button {
+                    class: "sort-button",
+                    title: "Process only the images that failed in the last run, into the same folder",
+                    onclick: retry_failed,
+                    disabled: processing() || loading_files(),
+                    "RETRY FAILED ({run.ids.len()})"
+                }
+            }
+            if processing() {
+                button {
+                    id: "cancel-button",
+                    title: "Stop after the images currently being written",
+                    onclick: move |_| cancel_flag.read().store(true, Ordering::Relaxed),
+                    "CANCEL"
+                }
+            }
+            button {
+                class: "sort-button",
+                title: "Sort by file name, numbers in order",
+                onclick: move |_| sort_images(SortOrder::Name),
+                disabled: processing() || loading_files(),
+                "SORT A–Z"
+            }
+            button {
+                class: "sort-button",
+                title: "Sort by date modified, oldest first",
+                onclick: move |_| sort_images(SortOrder::Modified),
+                disabled: processing() || loading_files(),
+                "SORT BY DATE"
+            }
+            button {
+                class: "sort-button",
+                title: "Sort by when each photo was taken (EXIF), oldest first, or by date modified when a file doesn't say; images with neither go last",
+                onclick: move |_| sort_images(SortOrder::Captured),
+                disabled: processing() || loading_files(),
+                "SORT BY CAPTURE"
+            }
+            if image_count > 0 {
+                span {
+                    id: "image-summary",
+                    title: "Images loaded and their combined size on disk",
+                    if image_count == 1 {
+                        "1 IMAGE · {total_size}"
+                    } else {
+                        "{image_count} IMAGES · {total_size}"
+                    }
+                }
+            }
+            div {
+                class: "setting",
+                label {
+                    r#for: "filter",
+                    "FILTER"
+                }
+                input {
+                    id: "filter",
+                    r#type: "search",
+                    placeholder: "File name",
+                    title: "Show only images whose file name contains this; numbering is unaffected",
+                    value: "{filter}",
+                    oninput: move |evt: FormEvent| filter.set(evt.value()),
+                }
+            }
+            if selected_count > 0 {
                 div {
-                    class: "empty-preview",
-                    "No images loaded. Click OPEN to select JPG files."
+                    class: "bulk-actions",
+                    span {
+                        class: "setting-value",
+                        "{selected_count} SELECTED"
+                    }
+                    button {
+                        onclick: remove_selected,
+                        disabled: processing() || loading_files(),
+                        "REMOVE"
+                    }
+                    button {
+                        onclick: move_selected_to_front,
+                        disabled: processing() || loading_files(),
+                        "TO FRONT"
+                    }
+                    button {
+                        onclick: move_selected_to_back,
+                        disabled: processing() || loading_files(),
+                        "TO BACK"
+                    }
+                    button {
+                        onclick: insert_files,
+                        disabled: processing() || loading_files(),
+                        title: "Add files right after the last selected image",
+                        "INSERT AFTER"
+                    }
+                    button {
+                        onclick: move |_| selected.write().clear(),
+                        "DESELECT"
+                    }
                 }
-            } else {
-                for (idx, item) in images().iter().enumerate() {
-                    ImageCard {
-                        key: "{idx}-{item.id}",
-                        item: item.clone(),
-                        drag_source,
-                        drag_over_id,
-                        images,
+            }
+            div {
+                class: "setting",
+                label {
+                    r#for: "split-mode",
+                    "SPLIT"
+                }
+                select {
+                    id: "split-mode",
+                    disabled: processing() || loading_files(),
+                    onchange: move |evt: FormEvent| {
+                        settings.write().output.split_mode = match evt.value().as_str() {
+                            "horizontal" => SplitMode::Horizontal,
+                            "quadrants" => SplitMode::Quadrants,
+                            _ => SplitMode::Vertical,
+                        };
+                    },
+                    option {
+                        value: "vertical",
+                        selected: output.split_mode == SplitMode::Vertical,
+                        "LEFT / RIGHT"
+                    }
+                    option {
+                        value: "horizontal",
+                        selected: output.split_mode == SplitMode::Horizontal,
+                        "TOP / BOTTOM"
+                    }
+                    option {
+                        value: "quadrants",
+                        selected: output.split_mode == SplitMode::Quadrants,
+                        "2 × 2"
                     }
                 }
             }
-        }
+            div {
+                class: "setting",
+                title: "Which half is numbered _1 in a left/right split",
+                label {
+                    r#for: "reading-order",
+                    "READING"
+                }
+                select {
+                    id: "reading-order",
+                    disabled: processing() || loading_files() || output.split_mode != SplitMode::Vertical,
+                    onchange: move |evt: FormEvent| {
+                        settings.write().output.reading_order = match evt.value().as_str() {
+                            "rtl" => ReadingOrder::RightToLeft,
+                            _ => ReadingOrder::LeftToRight,
+                        };
+                    },
+                    option {
+                        value: "ltr",
+                        selected: output.reading_order == ReadingOrder::LeftToRight,
+                        "LEFT → RIGHT"
+                    }
+                    option {
+                        value: "rtl",
+                        selected: output.reading_order == ReadingOrder::RightToLeft,
+                        "RIGHT → LEFT"
+                    }
+                }
+            }
+            div {
+                class: "setting",
+                label {
+                    r#for: "splits",
+                    "PARTS"
+                }
+                input {
+                    id: "splits",
+                    class: "number-input",
+                    r#type: "number",
+                    min: "2",
+                    max: "{MAX_SPLITS}",
+                    value: "{output.splits}",
+                    disabled: processing() || loading_files() || output.split_mode == SplitMode::Quadrants,
+                    oninput: move |evt: FormEvent| {
+                        if let Ok(value) = evt.value().parse::<u32>() {
+                            settings.write().output.splits = value.clamp(2, MAX_SPLITS);
+                        }
+                    },
+                }
+            }
+            div {
+                class: "setting",
+                label {
+                    r#for: "output-format",
+                    "FORMAT"
+                }
+                select {
+                    id: "output-format",
+                    disabled: processing() || loading_files(),
+                    onchange: move |evt: FormEvent| {
+                        settings.write().output.format = match evt.value().as_str() {
+                            "png" => OutputFormat::Png,
+                            _ => OutputFormat::Jpeg,
+                        };
+                    },
+                    option {
+                        value: "jpeg",
+                        selected: output.format == OutputFormat::Jpeg,
+                        "JPEG"
+                    }
+                    option {
+                        value: "png",
+                        selected: output.format == OutputFormat::Png,
+                        "PNG"
+                    }
+                }
+            }
+            div {
+                class: "setting",
+                title: "Write only the odd or even pages of the run, keeping the numbering of a full run",
+                label {
+                    r#for: "page-filter",
+                    "PAGES"
+                }
+                select {
+                    id: "page-filter",
+                    disabled: processing() || loading_files(),
+                    onchange: move |evt: FormEvent| {
+                        settings.write().output.page_filter = match evt.value().as_str() {
+                            "odd" => PageFilter::Odd,
+                            "even" => PageFilter::Even,
+                            _ => PageFilter::All,
+                        };
+                    },
+                    option {
+                        value: "all",
+                        selected: output.page_filter == PageFilter::All,
+                        "ALL"
+                    }
+                    option {
+                        value: "odd",
+                        selected: output.page_filter == PageFilter::Odd,
+                        "ODD ONLY"
+                    }
+                    option {
+                        value: "even",
+                        selected: output.page_filter == PageFilter::Even,
+                        "EVEN ONLY"
+                    }
+                }
+            }
+            div {
+                class: "setting",
+                title: "Pixels each piece extends past the cut, so nothing in the gutter is lost",
+                label {
+                    r#for: "overlap",
+                    "OVERLAP"
+                }
+                input {
+                    id: "overlap",
+                    class: "number-input",
+                    r#type: "number",
+                    min: "0",
+                    max: "{MAX_OVERLAP}",
+                    value: "{output.overlap}",
+                    disabled: processing() || loading_files(),
+                    oninput: move |evt: FormEvent| {
+                        if let Ok(value) = evt.value().parse::<u32>() {
+                            settings.write().output.overlap = value.min(MAX_OVERLAP);
+                        }
+                    },
+                }
+            }
+            div {
+                class: "setting",
+                label {
+                    r#for: "pdf-export",
+                    "PDF"
+                }
+                select {
+                    id: "pdf-export",
+                    disabled: processing() || loading_files(),
+                    onchange: move |evt: FormEvent| {
+                        settings.write().output.pdf_export = match evt.value().as_str() {
+                            "with-images" => PdfExport::WithImages,
+                            "only" => PdfExport::Only,
+                            _ => PdfExport::Off,
+                        };
+                    },
+                    option {
+                        value: "off",
+                        selected: output.pdf_export == PdfExport::Off,
+                        "OFF"
+                    }
+                    option {
+                        value: "with-images",
+                        selected: output.pdf_export == PdfExport::WithImages,
+                        "WITH IMAGES"
+                    }
+                    option {
+                        value: "only",
+                        selected: output.pdf_export == PdfExport::Only,
+                        "PDF ONLY"
+                    }
+                }
+            }
+            div {
+                class: "setting",
+                title: "Write manifest.csv into the output folder, recording which source file produced which outputs",
+                label {
+                    r#for: "write-manifest",
+                    "MANIFEST"
+                }
+                input {
+                    id: "write-manifest",
+                    r#type: "checkbox",
+                    checked: output.write_manifest,
+                    disabled: processing() || loading_files(),
+                    onchange: move |evt: FormEvent| settings.write().output.write_manifest = evt.checked(),
+                }
+            }
+            div {
+                class: "setting",
+                title: "Write grey output, which roughly halves JPEG size for text scans",
+                label {
+                    r#for: "grayscale",
+                    "GRAYSCALE"
+                }
+                input {
+                    id: "grayscale",
+                    r#type: "checkbox",
+                    checked: output.grayscale,
+                    disabled: processing() || loading_files(),
+                    onchange: move |evt: FormEvent| settings.write().output.grayscale = evt.checked(),
+                }
+            }
+            div {
+                class: "setting",
+                title: "Split only landscape spreads; portrait pages such as covers are saved whole as _1",
+                label {
+                    r#for: "landscape-only",
+                    "LANDSCAPE ONLY"
+                }
+                input {
+                    id: "landscape-only",
+                    r#type: "checkbox",
+                    checked: output.landscape_only,
+                    disabled: processing() || loading_files() || output.rename_only,
+                    onchange: move |evt: FormEvent| settings.write().output.landscape_only = evt.checked(),
+                }
+            }
+            div {
+                class: "setting",
+                title: "Skip splitting and just renumber: each image is saved whole as e.g. 001.jpg",
+                label {
+                    r#for: "rename-only",
+                    "RENAME ONLY"
+                }
+                input {
+                    id: "rename-only",
+                    r#type: "checkbox",
+                    checked: output.rename_only,
+                    disabled: processing() || loading_files(),
+                    onchange: move |evt: FormEvent| settings.write().output.rename_only = evt.checked(),
+                }
+            }
+            div {
+                class: "setting",
+                title: "Also save each full page as part 0, e.g. 001_0.jpg",
+                label {
+                    r#for: "keep-original",
+                    "KEEP ORIGINAL"
+                }
+                input {
+                    id: "keep-original",
+                    r#type: "checkbox",
+                    checked: output.keep_original,
+                    disabled: processing()
+                        || loading_files()
+                        || output.pdf_export == PdfExport::Only
+                        || output.rename_only,
+                    onchange: move |evt: FormEvent| settings.write().output.keep_original = evt.checked(),
+                }
+            }
+            div {
+                class: "setting",
+                title: "Rotate images as their EXIF orientation says; turn off for scans that are already upright",
+                label {
+                    r#for: "auto-orient",
+                    "AUTO-ROTATE"
+                }
+                input {
+                    id: "auto-orient",
+                    r#type: "checkbox",
+                    checked: output.auto_orient,
+                    disabled: processing() || loading_files(),
+                    onchange: move |evt: FormEvent| {
+                        settings.write().output.auto_orient = evt.checked();
+                        // Sideways orientations swap the upright size, including in the undo snapshots
+                        let swap_sideways = |imgs: &mut Vec<ImageItem>| {
+                            for img in imgs.iter_mut().filter(|img| img.orientation >= 5) {
+                                std::mem::swap(&mut img.width, &mut img.height);
+                            }
+                        };
+                        swap_sideways(&mut images.write());
+                        history.write().iter_mut().for_each(swap_sideways);
+                        spawn(refresh_thumbnails(images, settings.read().thumbnail_options()));
+                    },
+                }
+            }
+            div {
+                class: "setting",
+                title: "Crop white scanner margins before splitting; lower the threshold for greyer paper",
+                label {
+                    r#for: "auto-trim",
+                    "AUTO-TRIM"
+                }
+                input {
+                    id: "auto-trim",
+                    r#type: "checkbox",
+                    checked: output.auto_trim,
+                    disabled: processing() || loading_files(),
+                    onchange: move |evt: FormEvent| settings.write().output.auto_trim = evt.checked(),
+                }
+                input {
+                    id: "trim-threshold",
+                    class: "number-input",
+                    r#type: "number",
+                    min: "0",
+                    max: "255",
+                    value: "{output.trim_threshold}",
+                    disabled: processing() || loading_files() || !output.auto_trim,
+                    oninput: move |evt: FormEvent| {
+                        if let Ok(value) = evt.value().parse::<u8>() {
+                            settings.write().output.trim_threshold = value;
+                        }
+                    },
+                }
+            }
+            div {
+                class: "setting",
+                title: "Cut two-way splits at the darkest band within this share of the page around the centre. Dividers moved by hand are kept.",
+                label {
+                    r#for: "detect-gutter",
+                    "FIND GUTTER"
+                }
+                input {
+                    id: "detect-gutter",
+                    r#type: "checkbox",
+                    checked: output.detect_gutter,
+                    disabled: processing() || loading_files(),
+                    onchange: move |evt: FormEvent| settings.write().output.detect_gutter = evt.checked(),
+                }
+                input {
+                    id: "gutter-window",
+                    class: "number-input",
+                    r#type: "number",
+                    min: "1",
+                    max: "{MAX_GUTTER_WINDOW}",
+                    value: "{output.gutter_window}",
+                    disabled: processing() || loading_files() || !output.detect_gutter,
+                    oninput: move |evt: FormEvent| {
+                        if let Ok(value) = evt.value().parse::<u32>() {
+                            settings.write().output.gutter_window = value.clamp(1, MAX_GUTTER_WINDOW);
+                        }
+                    },
+                }
+                span {
+                    class: "setting-value",
+                    "%"
+                }
+            }
+            div {
+                class: "setting",
+                title: "On an odd width, drop the centre pixel column (x = width / 2, counting from 0) so both halves are the same width. Top/bottom splits drop the centre row.",
+                label {
+                    r#for: "balance-halves",
+                    "BALANCE HALVES"
+                }
+                input {
+                    id: "balance-halves",
+                    r#type: "checkbox",
+                    checked: output.balance_halves,
+                    disabled: processing() || loading_files(),
+                    onchange: move |evt: FormEvent| settings.write().output.balance_halves = evt.checked(),
+                }
+            }
+            div {
+                class: "setting",
+                title: "Mirror the left half (top half in a top/bottom split) of a two-way split, e.g. for transparency film scanned reversed",
+                label {
+                    r#for: "flip-left",
+                    "FLIP LEFT"
+                }
+                select {
+                    id: "flip-left",
+                    disabled: processing() || loading_files() || !has_halves,
+                    onchange: move |evt: FormEvent| {
+                        settings.write().output.flip_left = match evt.value().as_str() {
+                            "horizontal" => Flip::Horizontal,
+                            "vertical" => Flip::Vertical,
+                            "both" => Flip::Both,
+                            _ => Flip::None,
+                        };
+                    },
+                    option {
+                        value: "none",
+                        selected: output.flip_left == Flip::None,
+                        "NONE"
+                    }
+                    option {
+                        value: "horizontal",
+                        selected: output.flip_left == Flip::Horizontal,
+                        "MIRROR"
+                    }
+                    option {
+                        value: "vertical",
+                        selected: output.flip_left == Flip::Vertical,
+                        "UPSIDE DOWN"
+                    }
+                    option {
+                        value: "both",
+                        selected: output.flip_left == Flip::Both,
+                        "BOTH"
+                    }
+                }
+            }
+            div {
+                class: "setting",
+                title: "Mirror the right half (bottom half in a top/bottom split) of a two-way split, e.g. for transparency film scanned reversed",
+                label {
+                    r#for: "flip-right",
+                    "FLIP RIGHT"
+                }
+                select {
+                    id: "flip-right",
+                    disabled: processing() || loading_files() || !has_halves,
+                    onchange: move |evt: FormEvent| {
+                        settings.write().output.flip_right = match evt.value().as_str() {
+                            "horizontal" => Flip::Horizontal,
+                            "vertical" => Flip::Vertical,
+                            "both" => Flip::Both,
+                            _ => Flip::None,
+                        };
+                    },
+                    option {
+                        value: "none",
+                        selected: output.flip_right == Flip::None,
+                        "NONE"
+                    }
+                    option {
+                        value: "horizontal",
+                        selected: output.flip_right == Flip::Horizontal,
+                        "MIRROR"
+                    }
+                    option {
+                        value: "vertical",
+                        selected: output.flip_right == Flip::Vertical,
+                        "UPSIDE DOWN"
+                    }
+                    option {
+                        value: "both",
+                        selected: output.flip_right == Flip::Both,
+                        "BOTH"
+                    }
+                }
+            }
+            div {
+                class: "setting",
+                title: "Shrink output pages whose width or height exceeds this many pixels",
+                label {
+                    r#for: "limit-size",
+                    "MAX SIZE"
+                }
+                input {
+                    id: "limit-size",
+                    r#type: "checkbox",
+                    checked: output.limit_size,
+                    disabled: processing() || loading_files(),
+                    onchange: move |evt: FormEvent| settings.write().output.limit_size = evt.checked(),
+                }
+                input {
+                    id: "max-dimension",
+                    class: "number-input",
+                    r#type: "number",
+                    min: "1",
+                    value: "{output.max_dimension}",
+                    disabled: processing() || loading_files() || !output.limit_size,
+                    oninput: move |evt: FormEvent| {
+                        if let Ok(value) = evt.value().parse::<u32>() {
+                            settings.write().output.max_dimension = value.max(1);
+                        }
+                    },
+                }
+                span {
+                    class: "setting-value",
+                    "PX"
+                }
+            }
+            div {
+                class: "setting",
+                label {
+                    r#for: "quality",
+                    "QUALITY"
+                }
+                input {
+                    id: "quality",
+                    r#type: "range",
+                    min: "1",
+                    max: "100",
+                    value: "{output.quality}",
+                    disabled: processing() || loading_files() || !uses_jpeg,
+                    oninput: move |evt: FormEvent| {
+                        if let Ok(value) = evt.value().parse::<i64>() {
+                            settings.write().output.quality = clamp_quality(value);
+                        }
+                    },
+                }
+                span {
+                    class: "setting-value",
+                    "{output.quality}"
+                }
+            }
+            div {
+                class: "setting",
+                label {
+                    r#for: "dpi",
+                    "DPI"
+                }
+                select {
+                    id: "dpi",
+                    disabled: processing() || loading_files(),
+                    onchange: move |evt: FormEvent| {
+                        if let Ok(value) = evt.value().parse::<u16>() {
+                            settings.write().output.dpi = value;
+                        }
+                    },
+                    // A value set on the command line or in a project file is offered too
+                    for option_dpi in override_choices(DPI_OPTIONS, Some(output.dpi)) {
+                        option {
+                            value: "{option_dpi}",
+                            selected: output.dpi == option_dpi,
+                            "{option_dpi}"
+                        }
+                    }
+                }
+            }
+            div {
+                class: "setting",
+                title: "Write this text and the source file name into each JPEG's comment",
+                label {
+                    r#for: "embed-comment",
+                    "COMMENT"
+                }
+                input {
+                    id: "embed-comment",
+                    r#type: "checkbox",
+                    checked: output.embed_comment,
+                    disabled: processing() || loading_files() || !writes_jpeg_files,
+                    onchange: move |evt: FormEvent| settings.write().output.embed_comment = evt.checked(),
+                }
+                input {
+                    id: "comment",
+                    r#type: "text",
+                    placeholder: DEFAULT_COMMENT,
+                    value: "{output.comment}",
+                    disabled: processing() || loading_files() || !writes_jpeg_files || !output.embed_comment,
+                    oninput: move |evt: FormEvent| settings.write().output.comment = evt.value(),
+                }
+            }
+            div {
+                class: "setting",
+                label {
+                    r#for: "prefix",
+                    "PREFIX"
+                }
+                input {
+                    id: "prefix",
+                    r#type: "text",
+                    placeholder: "e.g. CH03_",
+                    value: "{output.prefix}",
+                    disabled: processing() || loading_files(),
+                    oninput: move |evt: FormEvent| {
+                        // The prefix ends up in file names, so drop anything a file system would reject
+                        settings.write().output.prefix = evt
+                            .value()
+                            .chars()
+                            .filter(|c| !INVALID_FILENAME_CHARS.contains(c))
+                            .collect();
+                    },
+                }
+            }
+            div {
+                class: "setting",
+                title: "Leave empty for the usual 001_1 names. Tokens: {{seq}} number, {{side}} part, {{orig}} source name, {{date}} source date, {{width}} source width. Needs {{seq}} or {{orig}}",
+                label {
+                    r#for: "name-template",
+                    "NAMES"
+                }
+                input {
+                    id: "name-template",
+                    r#type: "text",
+                    placeholder: "{{seq}}_{{side}}",
+                    value: "{output.name_template}",
+                    disabled: processing() || loading_files(),
+                    oninput: move |evt: FormEvent| {
+                        settings.write().output.name_template = evt
+                            .value()
+                            .chars()
+                            .filter(|c| !INVALID_FILENAME_CHARS.contains(c))
+                            .collect();
+                    },
+                }
+            }
+            div {
+                class: "setting",
+                label {
+                    r#for: "start-index",
+                    "START"
+                }
+                input {
+                    id: "start-index",
+                    class: "number-input",
+                    r#type: "number",
+                    min: "0",
+                    value: "{output.start_index}",
+                    disabled: processing() || loading_files(),
+                    oninput: move |evt: FormEvent| {
+                        if let Ok(value) = evt.value().parse::<usize>() {
+                            settings.write().output.start_index = value;
+                        }
+                    },
+                }
+                input {
+                    id: "continue-numbering",
+                    r#type: "checkbox",
+                    title: "After each run, start the next one at the number after its last image",
+                    checked: continue_numbering,
+                    disabled: processing() || loading_files(),
+                    onchange: move |evt: FormEvent| settings.write().continue_numbering = evt.checked(),
+                }
+                label {
+                    r#for: "continue-numbering",
+                    "CONTINUE"
+                }
+                if continue_numbering {
+                    button {
+                        class: "sort-button",
+                        title: "Start numbering from {DEFAULT_START_INDEX} again",
+                        disabled: processing() || loading_files(),
+                        onclick: move |_| settings.write().output.start_index = DEFAULT_START_INDEX,
+                        "RESET"
+                    }
+                }
+            }
+            div {
+                class: "setting",
+                label {
+                    r#for: "padding",
+                    "DIGITS"
+                }
+                input {
+                    id: "padding",
+                    class: "number-input",
+                    r#type: "number",
+                    min: "1",
+                    max: "{MAX_PADDING}",
+                    value: "{output.padding}",
+                    disabled: processing() || loading_files(),
+                    oninput: move |evt: FormEvent| {
+                        if let Ok(value) = evt.value().parse::<usize>() {
+                            settings.write().output.padding = value.clamp(1, MAX_PADDING);
+                        }
+                    },
+                }
+            }
+            div {
+                class: "setting",
+                label {
+                    r#for: "overwrite-policy",
+                    "EXISTING FILES"
+                }
+                select {
+                    id: "overwrite-policy",
+                    disabled: processing() || loading_files(),
+                    onchange: move |evt: FormEvent| {
+                        settings.write().output.overwrite_policy = match evt.value().as_str() {
+                            "skip" => OverwritePolicy::Skip,
+                            "rename" => OverwritePolicy::RenameWithSuffix,
+                            _ => OverwritePolicy::Overwrite,
+                        };
+                    },
+                    option {
+                        value: "overwrite",
+                        selected: output.overwrite_policy == OverwritePolicy::Overwrite,
+                        "OVERWRITE"
+                    }
+                    option {
+                        value: "skip",
+                        selected: output.overwrite_policy == OverwritePolicy::Skip,
+                        "SKIP"
+                    }
+                    option {
+                        value: "rename",
+                        selected: output.overwrite_policy == OverwritePolicy::RenameWithSuffix,
+                        "KEEP BOTH"
+                    }
+                }
+            }
+            div {
+                class: "setting",
+                label {
+                    r#for: "subfolder",
+                    "FOLDER NAME"
+                }
+                input {
+                    id: "subfolder",
+                    r#type: "text",
+                    placeholder: DEFAULT_SUBFOLDER,
+                    value: "{output.subfolder}",
+                    disabled: processing() || loading_files(),
+                    oninput: move |evt: FormEvent| {
+                        settings.write().output.subfolder = evt
+                            .value()
+                            .chars()
+                            .filter(|c| !INVALID_FILENAME_CHARS.contains(c))
+                            .collect();
+                    },
+                }
+                label {
+                    r#for: "timestamp-subfolder",
+                    "+ TIME"
+                }
+                input {
+                    id: "timestamp-subfolder",
+                    r#type: "checkbox",
+                    checked: output.timestamp_subfolder,
+                    disabled: processing() || loading_files(),
+                    onchange: move |evt: FormEvent| {
+                        settings.write().output.timestamp_subfolder = evt.checked();
+                    },
+                }
+                label {
+                    r#for: "zip-output",
+                    title: "Save everything in one ZIP file with this name instead of a folder",
+                    "ZIP"
+                }
+                input {
+                    id: "zip-output",
+                    r#type: "checkbox",
+                    checked: output.zip_output,
+                    disabled: processing() || loading_files(),
+                    onchange: move |evt: FormEvent| {
+                        settings.write().output.zip_output = evt.checked();
+                    },
+                }
+            }
+            div {
+                class: "setting",
+                title: "BY SOURCE gives each source folder its own subfolder, named after it. PER FOLDER starts the numbering again in each one",
+                label {
+                    r#for: "folder-structure",
+                    "SUBFOLDERS"
+                }
+                select {
+                    id: "folder-structure",
+                    disabled: processing() || loading_files(),
+                    onchange: move |evt: FormEvent| {
+                        settings.write().output.folder_structure = match evt.value().as_str() {
+                            "mirror" => FolderStructure::MirrorSource,
+                            _ => FolderStructure::Flat,
+                        };
+                    },
+                    option {
+                        value: "flat",
+                        selected: output.folder_structure == FolderStructure::Flat,
+                        "NONE"
+                    }
+                    option {
+                        value: "mirror",
+                        selected: output.folder_structure == FolderStructure::MirrorSource,
+                        "BY SOURCE"
+                    }
+                }
+                label {
+                    r#for: "number-per-folder",
+                    "PER FOLDER"
+                }
+                input {
+                    id: "number-per-folder",
+                    r#type: "checkbox",
+                    checked: output.number_per_folder,
+                    disabled: processing() || loading_files()
+                        || output.folder_structure == FolderStructure::Flat,
+                    onchange: move |evt: FormEvent| {
+                        settings.write().output.number_per_folder = evt.checked();
+                    },
+                }
+            }
+            div {
+                class: "setting",
+                title: "Brighten or darken the output; previews follow the slider",
+                label {
+                    r#for: "brightness",
+                    "BRIGHTNESS"
+                }
+                input {
+                    id: "brightness",
+                    r#type: "range",
+                    min: "-{MAX_TONE_ADJUSTMENT}",
+                    max: "{MAX_TONE_ADJUSTMENT}",
+                    value: "{output.brightness}",
+                    disabled: processing() || loading_files(),
+                    oninput: move |evt: FormEvent| {
+                        if let Ok(value) = evt.value().parse::<i32>() {
+                            settings.write().output.brightness =
+                                value.clamp(-MAX_TONE_ADJUSTMENT, MAX_TONE_ADJUSTMENT);
+                            refresh_live();
+                        }
+                    },
+                }
+                span {
+                    class: "setting-value",
+                    "{output.brightness}"
+                }
+            }
+            div {
+                class: "setting",
+                title: "Raise contrast to bring faded text back; previews follow the slider",
+                label {
+                    r#for: "contrast",
+                    "CONTRAST"
+                }
+                input {
+                    id: "contrast",
+                    r#type: "range",
+                    min: "-{MAX_TONE_ADJUSTMENT}",
+                    max: "{MAX_TONE_ADJUSTMENT}",
+                    value: "{output.contrast}",
+                    disabled: processing() || loading_files(),
+                    oninput: move |evt: FormEvent| {
+                        if let Ok(value) = evt.value().parse::<i32>() {
+                            settings.write().output.contrast =
+                                value.clamp(-MAX_TONE_ADJUSTMENT, MAX_TONE_ADJUSTMENT);
+                            refresh_live();
+                        }
+                    },
+                }
+                span {
+                    class: "setting-value",
+                    "{output.contrast}"
+                }
+            }
+            div {
+                class: "setting",
+                title: "Colour that transparent areas are filled with in JPEG output and previews; PNG output stays transparent",
+                label {
+                    r#for: "background",
+                    "BACKGROUND"
+                }
+                input {
+                    id: "background",
+                    r#type: "color",
+                    value: "{hex_color(output.background)}",
+                    disabled: processing() || loading_files(),
+                    oninput: move |evt: FormEvent| {
+                        if let Some(color) = parse_hex_color(&evt.value()) {
+                            settings.write().output.background = color;
+                            refresh_live();
+                        }
+                    },
+                }
+                if output.background != DEFAULT_BACKGROUND {
+                    button {
+                        class: "sort-button",
+                        title: "Back to white",
+                        disabled: processing() || loading_files(),
+                        onclick: move |_| {
+                            settings.write().output.background = DEFAULT_BACKGROUND;
+                            refresh_live();
+                        },
+                        "WHITE"
+                    }
+                }
+            }
+            div {
+                class: "setting",
+                title: "Lower preview quality keeps memory down on big batches; output files are unaffected",
+                label {
+                    r#for: "thumbnail-quality",
+                    "PREVIEW QUALITY"
+                }
+                input {
+                    id: "thumbnail-quality",
+                    r#type: "range",
+                    min: "10",
+                    max: "100",
+                    value: "{thumbnail_quality}",
+                    disabled: processing() || loading_files(),
+                    oninput: move |evt: FormEvent| {
+                        if let Ok(value) = evt.value().parse::<i64>() {
+                            settings.write().thumbnail_quality = clamp_quality(value);
+                            refresh_live();
+                        }
+                    },
+                }
+                span {
+                    class: "setting-value",
+                    "{thumbnail_quality}"
+                }
+            }
+            div {
+                class: "setting",
+                title: "Previews of images above this many megapixels are made without decoding them at full size (JPEG), or one at a time (other formats), so big folders don't run out of memory",
+                label {
+                    r#for: "large-image-megapixels",
+                    "LARGE IMAGES"
+                }
+                input {
+                    id: "large-image-megapixels",
+                    class: "number-input",
+                    r#type: "number",
+                    min: "1",
+                    max: "{MAX_LARGE_IMAGE_MEGAPIXELS}",
+                    value: "{large_image_megapixels}",
+                    disabled: processing() || loading_files(),
+                    oninput: move |evt: FormEvent| {
+                        if let Ok(value) = evt.value().parse::<u32>() {
+                            settings.write().large_image_megapixels =
+                                value.clamp(1, MAX_LARGE_IMAGE_MEGAPIXELS);
+                        }
+                    },
+                }
+                span {
+                    class: "setting-value",
+                    "MP"
+                }
+            }
+            div {
+                class: "setting",
+                label {
+                    r#for: "recursive-scan",
+                    "SUBFOLDERS"
+                }
+                input {
+                    id: "recursive-scan",
+                    r#type: "checkbox",
+                    checked: recursive_scan,
+                    disabled: processing() || loading_files(),
+                    onchange: move |evt: FormEvent| settings.write().recursive_scan = evt.checked(),
+                }
+            }
+            div {
+                class: "setting",
+                label {
+                    r#for: "view-mode",
+                    "VIEW"
+                }
+                select {
+                    id: "view-mode",
+                    onchange: move |evt: FormEvent| {
+                        settings.write().view_mode = match evt.value().as_str() {
+                            "list" => ViewMode::List,
+                            _ => ViewMode::Grid,
+                        };
+                    },
+                    option {
+                        value: "grid",
+                        selected: view_mode == ViewMode::Grid,
+                        "GRID"
+                    }
+                    option {
+                        value: "list",
+                        selected: view_mode == ViewMode::List,
+                        "LIST"
+                    }
+                }
+            }
+            div {
+                class: "setting",
+                label {
+                    r#for: "zoom",
+                    "ZOOM"
+                }
+                input {
+                    id: "zoom",
+                    r#type: "range",
+                    min: "{MIN_CARD_SIZE}",
+                    max: "{MAX_CARD_SIZE}",
+                    step: "10",
+                    value: "{card_size}",
+                    oninput: move |evt: FormEvent| {
+                        if let Ok(value) = evt.value().parse::<u32>() {
+                            settings.write().card_size = value.clamp(MIN_CARD_SIZE, MAX_CARD_SIZE);
+                        }
+                    },
+                }
+            }
+            div {
+                class: "setting",
+                label {
+                    r#for: "hires-previews",
+                    "HI-RES PREVIEWS"
+                }
+                input {
+                    id: "hires-previews",
+                    r#type: "checkbox",
+                    checked: hires_previews,
+                    disabled: processing() || loading_files(),
+                    onchange: move |evt: FormEvent| {
+                        settings.write().hires_previews = evt.checked();
+                        spawn(refresh_thumbnails(images, settings.read().thumbnail_options()));
+                    },
+                }
+            }
+            div {
+                class: "setting",
+                title: "FIT shows each whole image; FILL crops previews to the square for an even grid. Output isn't affected",
+                label {
+                    r#for: "preview-fit",
+                    "PREVIEW"
+                }
+                select {
+                    id: "preview-fit",
+                    disabled: processing() || loading_files(),
+                    onchange: move |evt: FormEvent| {
+                        settings.write().preview_fit = match evt.value().as_str() {
+                            "cover" => PreviewFit::Cover,
+                            _ => PreviewFit::Contain,
+                        };
+                        spawn(refresh_thumbnails(images, settings.read().thumbnail_options()));
+                    },
+                    option {
+                        value: "contain",
+                        selected: preview_fit == PreviewFit::Contain,
+                        "FIT"
+                    }
+                    option {
+                        value: "cover",
+                        selected: preview_fit == PreviewFit::Cover,
+                        "FILL"
+                    }
+                }
+            }
+            div {
+                class: "setting",
+                label {
+                    r#for: "threads",
+                    "THREADS"
+                }
+                input {
+                    id: "threads",
+                    class: "number-input",
+                    r#type: "number",
+                    min: "0",
+                    max: "{MAX_THREADS}",
+                    title: "0 uses one thread per CPU core",
+                    value: "{output.threads}",
+                    disabled: processing() || loading_files(),
+                    oninput: move |evt: FormEvent| {
+                        if let Ok(value) = evt.value().parse::<usize>() {
+                            settings.write().output.threads = value.min(MAX_THREADS);
+                        }
+                    },
+                }
+            }
+            div {
+                class: "setting",
+                title: "Open the output folder in the file manager when a run finishes",
+                label {
+                    r#for: "open-output",
+                    "OPEN WHEN DONE"
+                }
+                input {
+                    id: "open-output",
+                    r#type: "checkbox",
+                    checked: open_output,
+                    disabled: processing() || loading_files(),
+                    onchange: move |evt: FormEvent| settings.write().open_output = evt.checked(),
+                }
+            }
+            div {
+                class: "setting",
+                title: "Show a system notification when a run finishes, with how many images were processed and which failed",
+                label {
+                    r#for: "notify-when-done",
+                    "NOTIFY WHEN DONE"
+                }
+                input {
+                    id: "notify-when-done",
+                    r#type: "checkbox",
+                    checked: notify_when_done,
+                    disabled: processing() || loading_files(),
+                    onchange: move |evt: FormEvent| settings.write().notify_when_done = evt.checked(),
+                }
+            }
+            div {
+                class: "setting",
+                title: "{reuse_folder_hint}",
+                label {
+                    r#for: "reuse-save-folder",
+                    "SAME FOLDER"
+                }
+                input {
+                    id: "reuse-save-folder",
+                    r#type: "checkbox",
+                    checked: reuse_save_folder,
+                    disabled: processing() || loading_files() || !has_last_folder,
+                    onchange: move |evt: FormEvent| settings.write().reuse_save_folder = evt.checked(),
+                }
+            }
+            div {
+                class: "setting",
+                label {
+                    r#for: "dry-run",
+                    "DRY RUN"
+                }
+                input {
+                    id: "dry-run",
+                    r#type: "checkbox",
+                    checked: dry_run(),
+                    disabled: processing() || loading_files(),
+                    onchange: move |evt: FormEvent| dry_run.set(evt.checked()),
+                }
+            }
+        }
+    }
+}
+
+// A selection rectangle being dragged over the empty part of the preview, in client coordinates
+#[derive(Clone, Debug, PartialEq)]
+struct RubberBand {
+    start: (f64, f64),
+    end: (f64, f64),
+    // Where the preview's top-left corner was, for drawing the band inside it
+    origin: (f64, f64),
+    // Card boxes measured when the drag began
+    cards: Vec<(usize, dioxus::html::geometry::PixelsRect)>,
+    // What the band adds to: the previous selection for a Ctrl/Cmd-drag, otherwise nothing
+    base: HashSet<usize>,
+}
+
+impl RubberBand {
+    // Left, top, right and bottom edges
+    fn edges(&self) -> (f64, f64, f64, f64) {
+        (
+            self.start.0.min(self.end.0),
+            self.start.1.min(self.end.1),
+            self.start.0.max(self.end.0),
+            self.start.1.max(self.end.1),
+        )
+    }
+
+    // A press that barely moves is a click, which leaves the selection alone
+    fn dragged(&self) -> bool {
+        (self.end.0 - self.start.0).abs() > RUBBER_BAND_THRESHOLD
+            || (self.end.1 - self.start.1).abs() > RUBBER_BAND_THRESHOLD
+    }
+
+    // The base selection plus every card the band touches
+    fn selection(&self) -> HashSet<usize> {
+        let (left, top, right, bottom) = self.edges();
+        let touched = self.cards.iter().filter(|(_, rect)| {
+            left < rect.max_x()
+                && right > rect.min_x()
+                && top < rect.max_y()
+                && bottom > rect.min_y()
+        });
+        self.base
+            .iter()
+            .copied()
+            .chain(touched.map(|(id, _)| *id))
+            .collect()
+    }
+}
+
+#[component]
+fn ImagePreview(
+    images: Signal<Vec<ImageItem>>,
+    // Decides which split lines the cards draw
+    output: OutputOptions,
+    card_size: u32,
+    view_mode: ViewMode,
+    thumbnail_options: ThumbnailOptions,
+    drag_source: Signal<Option<usize>>,
+    drag_over_id: Signal<Option<usize>>,
+    history: Signal<Vec<Vec<ImageItem>>>,
+    mut selected: Signal<HashSet<usize>>,
+    processing: Signal<bool>,
+    mut loading_files: Signal<bool>,
+    notifications: Signal<Vec<Notification>>,
+    log: Signal<Vec<LogLine>>,
+    lightbox: Signal<Option<usize>>,
+    split_preview: Signal<Option<usize>>,
+    filter: String,
+) -> Element {
+    // Content width of the preview, updated as the window is resized
+    let mut preview_width = use_signal(|| None::<f64>);
+    // Mounted card elements by item id, so keyboard navigation can move focus between them
+    let card_refs = use_signal(HashMap::<usize, std::rc::Rc<MountedData>>::new);
+    let mut preview_ref = use_signal(|| None::<std::rc::Rc<MountedData>>);
+    let mut rubber_band = use_signal(|| None::<RubberBand>);
+    // Zooming only rescales the cards; thumbnails keep their generated resolution.
+    // Once the width is known the column count is fixed from it, so the cards stretch to fill each row.
+    let preview_style = match preview_width() {
+        Some(width) => format!(
+            "--card-size: {}px; grid-template-columns: repeat({}, 1fr);",
+            card_size,
+            grid_columns(width, card_size)
+        ),
+        None => format!("--card-size: {}px;", card_size),
+    };
+    let band_style = rubber_band
+        .read()
+        .as_ref()
+        .filter(|band| band.dragged())
+        .map(|band| {
+            let (left, top, right, bottom) = band.edges();
+            format!(
+                "left: {}px; top: {}px; width: {}px; height: {}px;",
+                left - band.origin.0,
+                top - band.origin.1,
+                right - left,
+                bottom - top
+            )
+        });
+    let shown: Vec<ImageItem> = images
+        .read()
+        .iter()
+        .filter(|item| matches_filter(item, &filter))
+        .cloned()
+        .collect();
+
+    rsx! {
+        div {
+            id: "image-preview",
+            class: if view_mode == ViewMode::List { "list-view" } else { "" },
+            style: preview_style,
+            onmounted: move |evt: MountedEvent| preview_ref.set(Some(evt.data())),
+            // Cards stop their own presses, so this only starts on the gaps between them
+            onmousedown: move |evt: MouseEvent| {
+                if evt.trigger_button() != Some(MouseButton::Primary) {
+                    return;
+                }
+                let Some(preview) = preview_ref() else {
+                    return;
+                };
+                // Keeps the drag from selecting text
+                evt.prevent_default();
+                let point = evt.client_coordinates();
+                let modifiers = evt.modifiers();
+                let base = if modifiers.ctrl() || modifiers.meta() {
+                    selected()
+                } else {
+                    HashSet::new()
+                };
+                // Filtered-out cards aren't mounted, so they can't be picked up
+                let cards: Vec<_> = card_refs
+                    .read()
+                    .iter()
+                    .map(|(id, card)| (*id, card.clone()))
+                    .collect();
+                spawn(async move {
+                    let Ok(bounds) = preview.get_client_rect().await else {
+                        return;
+                    };
+                    let mut boxes = Vec::with_capacity(cards.len());
+                    for (id, card) in cards {
+                        if let Ok(rect) = card.get_client_rect().await {
+                            boxes.push((id, rect));
+                        }
+                    }
+                    rubber_band.set(Some(RubberBand {
+                        start: (point.x, point.y),
+                        end: (point.x, point.y),
+                        origin: (bounds.origin.x, bounds.origin.y),
+                        cards: boxes,
+                        base,
+                    }));
+                });
+            },
+            onmousemove: move |evt: MouseEvent| {
+                if rubber_band.read().is_none() {
+                    return;
+                }
+                // The button may have been let go outside the window
+                if !evt.held_buttons().contains(MouseButton::Primary) {
+                    rubber_band.set(None);
+                    return;
+                }
+                let point = evt.client_coordinates();
+                let mut band = rubber_band.write();
+                let Some(band) = band.as_mut() else {
+                    return;
+                };
+                band.end = (point.x, point.y);
+                if band.dragged() {
+                    selected.set(band.selection());
+                }
+            },
+            onmouseup: move |_| rubber_band.set(None),
+            onresize: move |evt: ResizeEvent| {
+                if let Ok(size) = evt.get_content_box_size() {
+                    preview_width.set(Some(size.width));
+                }
+            },
+            ondragover: move |evt: DragEvent| {
+                evt.prevent_default();
+            },
+            // Files dragged in from the OS file manager are appended to the current set
+            ondrop: move |evt: DragEvent| {
+                let files = evt.files();
+                if files.is_empty() {
+                    return;
+                }
+                evt.prevent_default();
+                if processing() || loading_files() {
+                    return;
+                }
+
+                let paths: Vec<PathBuf> = files
+                    .iter()
+                    .map(|file| file.path())
+                    .filter(|path| has_supported_extension(path))
+                    .collect();
+                if paths.is_empty() {
+                    notify(
+                        notifications,
+                        "✗ No supported image files dropped".to_string(),
+                        NotificationType::Error,
+                    );
+                    return;
+                }
+
+                loading_files.set(true);
+                spawn(async move {
+                    load_images(
+                        paths,
+                        images,
+                        notifications,
+                        log,
+                        Placement::Append,
+                        thumbnail_options,
+                    )
+                    .await;
+                    loading_files.set(false);
+                });
+            },
+            if let Some(style) = band_style {
+                div {
+                    class: "rubber-band",
+                    style,
+                }
+            }
+            if images().is_empty() {
+                div {
+                    class: "empty-preview",
+                    "No images loaded. Click OPEN to select JPG, PNG, TIFF or WebP files, or drop them here."
+                }
+            } else if shown.is_empty() {
+                div {
+                    class: "empty-preview",
+                    "No file names contain “{filter}”."
+                }
+            } else {
+                for item in shown.iter() {
+                    ImageCard {
+                        key: "{item.id}",
+                        item: item.clone(),
+                        output: output.clone(),
+                        view_mode,
+                        thumbnail_options,
+                        drag_source,
+                        drag_over_id,
+                        images,
+                        history,
+                        selected,
+                        notifications,
+                        lightbox,
+                        split_preview,
+                        card_refs,
+                    }
+                }
+            }
+        }
+    }
+}
+
+#[component]
+fn ImageCard(
+    item: ImageItem,
+    output: OutputOptions,
+    view_mode: ViewMode,
+    thumbnail_options: ThumbnailOptions,
+    drag_source: Signal<Option<usize>>,
+    drag_over_id: Signal<Option<usize>>,
+    images: Signal<Vec<ImageItem>>,
+    history: Signal<Vec<Vec<ImageItem>>>,
+    mut selected: Signal<HashSet<usize>>,
+    notifications: Signal<Vec<Notification>>,
+    mut lightbox: Signal<Option<usize>>,
+    mut split_preview: Signal<Option<usize>>,
+    mut card_refs: Signal<HashMap<usize, std::rc::Rc<MountedData>>>,
+) -> Element {
+    let item_id = item.id;
+    use_drop(move || {
+        card_refs.write().remove(&item_id);
+    });
+
+    // Give keyboard focus to the card `target` picks from the current order and this card's index
+    let focus_card = move |target: fn(usize, usize) -> Option<usize>| {
+        let imgs = images.read();
+        let Some(idx) = imgs.iter().position(|img| img.id == item_id) else {
+            return;
+        };
+        let Some(card) = target(idx, imgs.len())
+            .and_then(|target_idx| imgs.get(target_idx))
+            .and_then(|img| card_refs.read().get(&img.id).cloned())
+        else {
+            return;
+        };
+        spawn(async move {
+            let _ = card.set_focus(true).await;
+        });
+    };
+    let is_drag_over = drag_over_id() == Some(item_id);
+    let is_selected = selected.read().contains(&item_id);
+    let thumbnail = item.thumbnail_base64.clone();
+    // Cards are shown as soon as a load starts; until the thumbnail and size arrive they're placeholders
+    let is_loading = thumbnail.is_empty() || item.width == 0;
+    let item_name = item_name(&item);
+    // Report the size as it will be split, i.e. after the user's rotation
+    let (width, height) = rotated_dimensions(&item);
+    let file_size = format_file_size(item.file_size);
+    // The card's own split mode wins over the one passed down from the settings
+    let split_override = item.split_mode;
+    let split_mode = split_override.unwrap_or(output.split_mode);
+    let splits = output.splits;
+    let quality_override = item.quality;
+    let dpi_override = item.dpi;
+    let output_override = match (quality_override, dpi_override) {
+        (Some(quality), Some(dpi)) => Some(format!("Q{quality} · {dpi}")),
+        (Some(quality), None) => Some(format!("Q{quality}")),
+        (None, Some(dpi)) => Some(format!("{dpi} DPI")),
+        (None, None) => None,
+    };
+    let quality_choices = override_choices(QUALITY_OVERRIDE_OPTIONS, quality_override);
+    let dpi_choices = override_choices(DPI_OPTIONS, dpi_override);
+    // A Cover preview is square and shows only the middle of the image, so the frame is square too
+    // and the divider is placed within the visible part
+    let cover = thumbnail_options.fit == PreviewFit::Cover;
+    let (window_start, window_length) = if cover {
+        cover_window(width, height, split_mode)
+    } else {
+        (0.0, 1.0)
+    };
+    // With gutter detection on, the line shows where the gutter is found in the preview, so a
+    // whole batch can be checked at a glance. The preview is small, but the cut lands close by.
+    let gutter_window =
+        (output.detect_gutter && splits == 2 && !is_loading).then_some(output.gutter_window);
+    let preview = thumbnail.clone();
+    let detected_gutter = use_memo(use_reactive!(|(preview, split_mode, gutter_window)| {
+        gutter_window.and_then(|window| preview_gutter(&preview, split_mode, window))
+    }))()
+    .map(|ratio| window_start + ratio * window_length);
+    // Cuts outside what a Cover preview shows aren't drawn
+    let cut_percents: Vec<f32> = preview_cuts(&item, &output, detected_gutter)
+        .into_iter()
+        .map(|cut| (cut - window_start) / window_length * 100.0)
+        .filter(|percent| (0.0..=100.0).contains(percent))
+        .collect();
+    let (frame_width, frame_height) = if cover { (1, 1) } else { (width, height) };
+    // Quadrant cuts are fixed at the centre and strips of more than two are equal,
+    // so only a single cut gets a draggable divider
+    let draggable_divider = splits == 2 && split_mode != SplitMode::Quadrants;
+    let split_percent = cut_percents.first().copied().unwrap_or(50.0);
+    let gutter_shown =
+        draggable_divider && detected_gutter.is_some() && item.split_ratio == DEFAULT_SPLIT_RATIO;
+    let divider_class = match split_mode {
+        SplitMode::Vertical | SplitMode::Quadrants => "split-divider vertical",
+        SplitMode::Horizontal => "split-divider horizontal",
+    };
+    let frame_style = match view_mode {
+        // The card is square, so this fits the frame inside 85% × 75% of it at the image's aspect ratio
+        ViewMode::Grid => format!(
+            "aspect-ratio: {} / {}; width: min(85%, calc(75% * {} / {}));",
+            frame_width, frame_height, frame_width, frame_height
+        ),
+        // List rows have a fixed height, so the frame's width follows from the aspect ratio
+        ViewMode::List => format!(
+            "aspect-ratio: {} / {}; height: 100%;",
+            frame_width, frame_height
+        ),
+    };
+    let divider_position = match split_mode {
+        SplitMode::Vertical | SplitMode::Quadrants => "left",
+        SplitMode::Horizontal => "top",
+    };
+    let divider_style = format!("{}: {}%;", divider_position, split_percent);
+
+    // Handler to move the item left (earlier in the list)
+    let move_left = move |_| shift_item(images, history, item_id, -1);
+
+    // Handler to move the item right (later in the list)
+    let move_right = move |_| shift_item(images, history, item_id, 1);
+
+    // Jump straight to either end of the list; `move_item` clamps the index to the last slot
+    let move_to_front = move |_| move_item(images, history, item_id, 0);
+    let move_to_back = move |_| move_item(images, history, item_id, usize::MAX);
+
+    // Rotate by `delta` degrees clockwise and regenerate the preview to match
+    let mut rotate = move |delta: u16| {
+        let Some(current) = images
+            .read()
+            .iter()
+            .find(|img| img.id == item_id)
+            .map(|img| img.rotation)
+        else {
+            return;
+        };
+        let rotation = (current + delta) % 360;
+
+        push_history(history, images.read().clone());
+        if let Some(img) = images.write().iter_mut().find(|img| img.id == item_id) {
+            img.rotation = rotation;
+        }
+        spawn(refresh_thumbnail(images, item_id, thumbnail_options));
+    };
+
+    // The divider is placed from pointer positions relative to the thumbnail, measured when a drag starts
+    let mut thumbnail_frame = use_signal(|| None::<std::rc::Rc<MountedData>>);
+    let mut divider_drag = use_signal(|| None::<dioxus::html::geometry::PixelsRect>);
+
+    let mut set_split_ratio = move |ratio: f32| {
+        if let Some(img) = images.write().iter_mut().find(|img| img.id == item_id) {
+            img.split_ratio = ratio.clamp(MIN_SPLIT_RATIO, MAX_SPLIT_RATIO);
+        }
+    };
+
+    let start_divider_drag = move |evt: MouseEvent| {
+        // Keep the card itself from starting a reorder drag
+        evt.prevent_default();
+        evt.stop_propagation();
+        let Some(frame) = thumbnail_frame() else {
+            return;
+        };
+        push_history(history, images.read().clone());
+        spawn(async move {
+            if let Ok(rect) = frame.get_client_rect().await {
+                divider_drag.set(Some(rect));
+            }
+        });
+    };
+
+    let move_divider = move |evt: MouseEvent| {
+        let Some(rect) = divider_drag() else {
+            return;
+        };
+        let point = evt.client_coordinates();
+        let position = match split_mode {
+            SplitMode::Vertical | SplitMode::Quadrants => {
+                (point.x - rect.origin.x) / rect.size.width
+            }
+            SplitMode::Horizontal => (point.y - rect.origin.y) / rect.size.height,
+        };
+        if position.is_finite() {
+            set_split_ratio(window_start + position as f32 * window_length);
+        }
+    };
+
+    // Drop just this item from the list
+    let mut remove = move || {
+        let imgs = images.read().clone();
+        push_history(history, imgs.clone());
+        images.set(imgs.into_iter().filter(|img| img.id != item_id).collect());
+        selected.write().remove(&item_id);
+
+        // A removed card can't finish a drag, so forget any drag state pointing at it
+        if drag_source() == Some(item_id) {
+            drag_source.set(None);
+        }
+        if drag_over_id() == Some(item_id) {
+            drag_over_id.set(None);
+        }
+    };
+
+    // Right-clicking a card opens a menu at the pointer with the less common actions
+    let mut context_menu = use_signal(|| None::<(f64, f64)>);
+
+    // Quality and DPI for this image's output only; None goes back to the batch setting
+    let mut set_output_override = move |quality: Option<u8>, dpi: Option<u16>| {
+        push_history(history, images.read().clone());
+        if let Some(img) = images.write().iter_mut().find(|img| img.id == item_id) {
+            img.quality = quality;
+            img.dpi = dpi;
+        }
+    };
+
+    // An image inside a ZIP has no file of its own for another app to open
+    let in_archive = archive::split(&item.path).is_some();
+    let item_path = item.path.clone();
+    let open_in_viewer = move |_| {
+        let path = item_path.clone();
+        spawn(async move {
+            let result = tokio::task::spawn_blocking(move || opener::open(&path)).await;
+            if !matches!(result, Ok(Ok(()))) {
+                notify(
+                    notifications,
+                    "✗ Could not open the image in the system viewer".to_string(),
+                    NotificationType::Error,
+                );
+            }
+        });
+    };
+
+    rsx! {
+        div {
+            class: "image-item",
+            class: if is_drag_over { "drag-over" } else { "" },
+            class: if is_selected { "selected" } else { "" },
+            draggable: true,
+            tabindex: "0",
+            // Ctrl/Cmd-click toggles the card in the multi-selection used by the bulk actions
+            onclick: move |evt: MouseEvent| {
+                let modifiers = evt.modifiers();
+                if modifiers.ctrl() || modifiers.meta() {
+                    let mut selection = selected.write();
+                    if !selection.remove(&item_id) {
+                        selection.insert(item_id);
+                    }
+                }
+            },
+            onmounted: move |evt: MountedEvent| {
+                card_refs.write().insert(item_id, evt.data());
+            },
+            // A press on a card is for the card, not the preview's rubber-band selection
+            onmousedown: move |evt: MouseEvent| evt.stop_propagation(),
+            onkeydown: move |evt: KeyboardEvent| {
+                // Arrow keys move focus between cards; with Ctrl/Cmd they move the focused card instead.
+                // Delete or Backspace removes the focused card.
+                let modifiers = evt.modifiers();
+                let reorder = modifiers.ctrl() || modifiers.meta();
+                match evt.key() {
+                    Key::ArrowLeft if reorder => shift_item(images, history, item_id, -1),
+                    Key::ArrowRight if reorder => shift_item(images, history, item_id, 1),
+                    Key::ArrowLeft => focus_card(|idx, _| idx.checked_sub(1)),
+                    Key::ArrowRight => focus_card(|idx, _| Some(idx + 1)),
+                    Key::Home => focus_card(|_, _| Some(0)),
+                    Key::End => focus_card(|_, len| len.checked_sub(1)),
+                    // Focus passes to the next card, or the previous one at the end, so several can
+                    // be deleted in a row; it moves once this card is gone
+                    Key::Delete | Key::Backspace => {
+                        focus_card(|idx, len| {
+                            if idx + 1 < len {
+                                Some(idx + 1)
+                            } else {
+                                idx.checked_sub(1)
+                            }
+                        });
+                        remove();
+                    }
+                    _ => return,
+                }
+                evt.prevent_default();
+            },
+            ondragstart: move |_| {
+                drag_source.set(Some(item_id));
+            },
+            ondragover: move |evt: DragEvent| {
+                evt.prevent_default();
+                drag_over_id.set(Some(item_id));
+            },
+            ondrop: move |evt: DragEvent| {
+                evt.prevent_default();
+
+                // The dragged card takes the target's position and the cards in between shift over
+                if let Some(source_id) = drag_source() {
+                    if source_id != item_id {
+                        let target_idx = images.read().iter().position(|img| img.id == item_id);
+                        if let Some(tgt) = target_idx {
+                            move_item(images, history, source_id, tgt);
+                        }
+                    }
+                }
+                drag_source.set(None);
+                drag_over_id.set(None);
+            },
+            ondragleave: move |_| {
+                drag_over_id.set(None);
+            },
+            oncontextmenu: move |evt: MouseEvent| {
+                evt.prevent_default();
+                let point = evt.client_coordinates();
+                context_menu.set(Some((point.x, point.y)));
+            },
+
+            if let Some((x, y)) = context_menu() {
+                // Clicking anywhere outside the menu closes it without doing anything
+                div {
+                    class: "context-menu-backdrop",
+                    onclick: move |evt: MouseEvent| {
+                        evt.stop_propagation();
+                        context_menu.set(None);
+                    },
+                    oncontextmenu: move |evt: MouseEvent| {
+                        evt.prevent_default();
+                        evt.stop_propagation();
+                        context_menu.set(None);
+                    },
+                }
+                div {
+                    class: "context-menu",
+                    style: "left: {x}px; top: {y}px;",
+                    // Every entry closes the menu once its action has run
+                    onclick: move |evt: MouseEvent| {
+                        evt.stop_propagation();
+                        context_menu.set(None);
+                    },
+                    button {
+                        onclick: move_to_front,
+                        "Move to front"
+                    }
+                    button {
+                        onclick: move_to_back,
+                        "Move to back"
+                    }
+                    button {
+                        onclick: move |_| rotate(270),
+                        "Rotate left"
+                    }
+                    button {
+                        onclick: move |_| rotate(90),
+                        "Rotate right"
+                    }
+                    button {
+                        onclick: move |_| split_preview.set(Some(item_id)),
+                        "Preview split"
+                    }
+                    if !in_archive {
+                        button {
+                            onclick: open_in_viewer,
+                            "Open in system viewer"
+                        }
+                    }
+                    // Advanced: output settings for this image alone. Changing them keeps the menu open.
+                    div {
+                        class: "context-menu-advanced",
+                        onclick: move |evt: MouseEvent| evt.stop_propagation(),
+                        label {
+                            "QUALITY"
+                            select {
+                                onchange: move |evt: FormEvent| {
+                                    let quality = evt.value().parse::<i64>().ok().map(clamp_quality);
+                                    set_output_override(quality, dpi_override);
+                                },
+                                option {
+                                    value: "global",
+                                    selected: quality_override.is_none(),
+                                    "AUTO ({output.quality})"
+                                }
+                                for option_quality in quality_choices.iter().copied() {
+                                    option {
+                                        value: "{option_quality}",
+                                        selected: quality_override == Some(option_quality),
+                                        "{option_quality}"
+                                    }
+                                }
+                            }
+                        }
+                        label {
+                            "DPI"
+                            select {
+                                onchange: move |evt: FormEvent| {
+                                    let dpi = evt.value().parse::<u16>().ok();
+                                    set_output_override(quality_override, dpi);
+                                },
+                                option {
+                                    value: "global",
+                                    selected: dpi_override.is_none(),
+                                    "AUTO ({output.dpi})"
+                                }
+                                for option_dpi in dpi_choices.iter().copied() {
+                                    option {
+                                        value: "{option_dpi}",
+                                        selected: dpi_override == Some(option_dpi),
+                                        "{option_dpi}"
+                                    }
+                                }
+                            }
+                        }
+                    }
+                    if output_override.is_some() {
+                        button {
+                            onclick: move |_| set_output_override(None, None),
+                            "Use batch quality and DPI"
+                        }
+                    }
+                    button {
+                        class: "danger",
+                        onclick: move |_| remove(),
+                        "Remove"
+                    }
+                }
+            }
+
+            // Control row with SVG arrows
+            div {
+                class: "move-buttons",
+                // Move to front button (SVG)
+                button {
+                    onclick: move_to_front,
+                    title: "Move to front",
+                    aria_label: "Move to front",
+                    svg {
+                        xmlns: "http://www.w3.org/2000/svg",
+                        view_box: "0 0 24 24",
+                        width: "14",
+                        height: "14",
+                        fill: "white",
+                        path {
+                            d: "M18.41 16.59L13.82 12l4.59-4.59L17 6l-6 6 6 6zM6 6h2v12H6z"
+                        }
+                    }
+                }
+                // Left arrow button (SVG)
+                button {
+                    onclick: move_left,
+                    title: "Move left",
+                    aria_label: "Move left",
+                    svg {
+                        xmlns: "http://www.w3.org/2000/svg",
+                        view_box: "0 0 24 24",
+                        width: "14",
+                        height: "14",
+                        fill: "white",
+                        path {
+                            d: "M15.41 7.41L14 6l-6 6 6 6 1.41-1.41L10.83 12z"
+                        }
+                    }
+                }
+                // Right arrow button (SVG)
+                button {
+                    onclick: move_right,
+                    title: "Move right",
+                    aria_label: "Move right",
+                    fill: "white",
+                    svg {
+                        xmlns: "http://www.w3.org/2000/svg",
+                        view_box: "0 0 24 24",
+                        width: "14",
+                        height: "14",
+                        path {
+                            d: "M8.59 16.59L10 18l6-6-6-6-1.41 1.41L13.17 12z"
+                        }
+                    }
+                }
+                // Move to back button (SVG)
+                button {
+                    onclick: move_to_back,
+                    title: "Move to back",
+                    aria_label: "Move to back",
+                    svg {
+                        xmlns: "http://www.w3.org/2000/svg",
+                        view_box: "0 0 24 24",
+                        width: "14",
+                        height: "14",
+                        fill: "white",
+                        path {
+                            d: "M5.59 7.41L10.18 12l-4.59 4.59L7 18l6-6-6-6zM16 6h2v12h-2z"
+                        }
+                    }
+                }
+                // Rotate counter-clockwise button (SVG)
+                button {
+                    onclick: move |_| rotate(270),
+                    title: "Rotate left",
+                    aria_label: "Rotate left",
+                    svg {
+                        xmlns: "http://www.w3.org/2000/svg",
+                        view_box: "0 0 24 24",
+                        width: "14",
+                        height: "14",
+                        fill: "white",
+                        path {
+                            d: "M7.11 8.53L5.7 7.11C4.8 8.27 4.24 9.61 4.07 11h2.02c.14-.87.49-1.72 1.02-2.47zM6.09 13H4.07c.17 1.39.72 2.73 1.62 3.89l1.41-1.42c-.52-.75-.87-1.59-1.01-2.47zm1.01 5.32c1.16.9 2.51 1.44 3.9 1.61V17.9c-.87-.15-1.71-.49-2.46-1.03L7.1 18.32zM13 4.07V1L8.45 5.55 13 10V6.09c2.84.48 5 2.94 5 5.91s-2.16 5.43-5 5.91v2.02c3.95-.49 7-3.85 7-7.93s-3.05-7.44-7-7.93z"
+                        }
+                    }
+                }
+                // Rotate clockwise button (SVG)
+                button {
+                    onclick: move |_| rotate(90),
+                    title: "Rotate right",
+                    aria_label: "Rotate right",
+                    svg {
+                        xmlns: "http://www.w3.org/2000/svg",
+                        view_box: "0 0 24 24",
+                        width: "14",
+                        height: "14",
+                        fill: "white",
+                        path {
+                            d: "M15.55 5.55L11 1v3.07C7.06 4.56 4 7.92 4 12s3.05 7.44 7 7.93v-2.02c-2.84-.48-5-2.94-5-5.91s2.16-5.43 5-5.91V10l4.55-4.45zM19.93 11c-.17-1.39-.72-2.73-1.62-3.89l-1.42 1.42c.54.75.88 1.6 1.02 2.47h2.02zM13 17.9v2.02c1.39-.17 2.74-.71 3.9-1.61l-1.44-1.44c-.75.54-1.59.89-2.46 1.03zm3.89-2.42l1.42 1.41c.9-1.16 1.45-2.5 1.62-3.89h-2.02c-.14.87-.48 1.72-1.02 2.48z"
+                        }
+                    }
+                }
+                // Remove button
+                button {
+                    class: "remove-button",
+                    onclick: move |_| remove(),
+                    title: "Remove",
+                    aria_label: "Remove",
+                    "×"
+                }
+            }
+
+            if is_loading {
+                div {
+                    class: "thumbnail-skeleton",
+                }
+            } else {
+                div {
+                    class: "thumbnail-frame",
+                    style: frame_style,
+                    onmounted: move |evt: MountedEvent| thumbnail_frame.set(Some(evt.data())),
+                    onmousemove: move_divider,
+                    onmouseup: move |_| divider_drag.set(None),
+                    onmouseleave: move |_| divider_drag.set(None),
+                    img {
+                        src: "data:image/jpeg;base64,{thumbnail}",
+                        alt: "Preview",
+                        title: "Click to view full size",
+                        // Ctrl/Cmd-clicks are left to the card's selection toggle
+                        onclick: move |evt: MouseEvent| {
+                            let modifiers = evt.modifiers();
+                            if !(modifiers.ctrl() || modifiers.meta()) {
+                                lightbox.set(Some(item_id));
+                            }
+                        },
+                    }
+                    if split_mode == SplitMode::Quadrants {
+                        if should_split(width, height, &output) {
+                            div {
+                                class: "split-divider vertical fixed",
+                                style: "left: 50%;",
+                            }
+                            div {
+                                class: "split-divider horizontal fixed",
+                                style: "top: 50%;",
+                            }
+                        }
+                    } else if !draggable_divider {
+                        for percent in cut_percents.iter() {
+                            div {
+                                class: "{divider_class} fixed",
+                                style: "{divider_position}: {percent}%;",
+                            }
+                        }
+                    } else if !cut_percents.is_empty() {
+                        div {
+                            class: "{divider_class}",
+                            class: if gutter_shown { "detected" } else { "" },
+                            style: divider_style,
+                            title: if gutter_shown {
+                                "Cut at the detected gutter. Drag to place it by hand, double-click to reset"
+                            } else {
+                                "Drag to move the split line, double-click to reset"
+                            },
+                            onmousedown: start_divider_drag,
+                            ondoubleclick: move |evt: MouseEvent| {
+                                evt.stop_propagation();
+                                push_history(history, images.read().clone());
+                                set_split_ratio(DEFAULT_SPLIT_RATIO);
+                            },
+                        }
+                    }
+                }
+            }
+            select {
+                class: "split-override",
+                class: if split_override.is_some() { "active" } else { "" },
+                title: if split_override.is_some() {
+                    "This image has its own split mode"
+                } else {
+                    "Split like the rest of the batch, or pick a mode for this image only"
+                },
+                onclick: move |evt: MouseEvent| evt.stop_propagation(),
+                onchange: move |evt: FormEvent| {
+                    let mode = match evt.value().as_str() {
+                        "vertical" => Some(SplitMode::Vertical),
+                        "horizontal" => Some(SplitMode::Horizontal),
+                        "quadrants" => Some(SplitMode::Quadrants),
+                        _ => None,
+                    };
+                    push_history(history, images.read().clone());
+                    if let Some(img) = images.write().iter_mut().find(|img| img.id == item_id) {
+                        img.split_mode = mode;
+                    }
+                },
+                option {
+                    value: "global",
+                    selected: split_override.is_none(),
+                    "AUTO"
+                }
+                option {
+                    value: "vertical",
+                    selected: split_override == Some(SplitMode::Vertical),
+                    "L | R"
+                }
+                option {
+                    value: "horizontal",
+                    selected: split_override == Some(SplitMode::Horizontal),
+                    "T / B"
+                }
+                option {
+                    value: "quadrants",
+                    selected: split_override == Some(SplitMode::Quadrants),
+                    "2 × 2"
+                }
+            }
+            if let Some(values) = output_override {
+                span {
+                    class: "output-override-badge",
+                    title: "Written at its own quality and DPI; right-click to change",
+                    "{values}"
+                }
+            }
+            if item.orientation > 1 {
+                span {
+                    class: "orientation-badge",
+                    class: if !thumbnail_options.auto_orient { "ignored" } else { "" },
+                    title: if thumbnail_options.auto_orient {
+                        "Rotated upright from EXIF orientation {item.orientation}"
+                    } else {
+                        "EXIF orientation {item.orientation} ignored, showing the raw pixels"
+                    },
+                    "EXIF"
+                }
+            }
+            div {
+                class: "image-label",
+                if is_loading {
+                    "{item_name}"
+                } else {
+                    "{item_name} — {width}×{height}, {file_size}"
+                }
+            }
+        }
+    }
+}
+
+// Decode an image by sniffing its header instead of trusting the file extension,
+// so misnamed scans still load as long as they are JPEG, PNG, TIFF or WebP (or HEIC with that feature).
+// With `auto_orient` the EXIF orientation is applied so previews and output match what a photo viewer shows.
+// A `page` picks one image out of a multi-page TIFF, oriented by that page's own tag.
+fn open_image(
+    path: &Path,
+    page: Option<usize>,
+    auto_orient: bool,
+) -> Result<image::DynamicImage, IrsError> {
+    if let Some(page) = page {
+        let (img, orientation) = tiff_pages::decode(path, page)?;
+        return Ok(if auto_orient {
+            apply_orientation(img, orientation)
+        } else {
+            img
+        });
+    }
+
+    #[cfg(feature = "heic")]
+    if heic::is_heif(path) {
+        return heic::decode(path);
+    }
+
+    let reader = image::io::Reader::new(archive::open(path)?).with_guessed_format()?;
+    let img = match reader.format() {
+        Some(format) if is_supported_format(format) => reader.decode().map_err(IrsError::decode)?,
+        Some(format) => {
+            return Err(IrsError::decode(format!(
+                "Unsupported image format: {:?}",
+                format
+            )))
+        }
+        None => return Err(IrsError::decode("Unrecognized image format")),
+    };
+    if !auto_orient {
+        return Ok(img);
+    }
+    Ok(apply_orientation(img, read_orientation(path)))
+}
+
+// Pixel dimensions, byte length and modification time of an image, from the header only so cached thumbnails stay cheap.
+// Orientations 5-8 turn the image on its side, so with `auto_orient` the reported size is swapped to match.
+fn read_image_info(
+    path: &Path,
+    page: Option<usize>,
+    auto_orient: bool,
+) -> Result<ImageInfo, IrsError> {
+    let (file_size, modified) = archive::metadata(path)?;
+
+    #[cfg(feature = "heic")]
+    if heic::is_heif(path) {
+        let (width, height) = heic::dimensions(path)?;
+        return Ok(ImageInfo {
+            width,
+            height,
+            file_size,
+            modified,
+            captured: read_capture_date(path),
+            // Already applied by libheif, so there's nothing left to report
+            orientation: 1,
+        });
+    }
+
+    let (width, height, orientation) = match page {
+        Some(page) => tiff_pages::dimensions(path, page)?,
+        None => {
+            let (width, height) = image::io::Reader::new(archive::open(path)?)
+                .with_guessed_format()?
+                .into_dimensions()
+                .map_err(IrsError::decode)?;
+            (width, height, read_orientation(path))
+        }
+    };
+    let (width, height) = if auto_orient && orientation >= 5 {
+        (height, width)
+    } else {
+        (width, height)
+    };
+    Ok(ImageInfo {
+        width,
+        height,
+        file_size,
+        modified,
+        captured: read_capture_date(path),
+        orientation,
+    })
+}
+
+// The embedded ICC profile, if the source has one that still applies after the RGB conversion
+// done on save. CMYK and greyscale profiles would misdescribe the output, so they're dropped.
+fn read_icc_profile(path: &Path) -> Option<Vec<u8>> {
+    use image::ImageDecoder;
+
+    let reader = image::io::Reader::new(archive::open(path).ok()?)
+        .with_guessed_format()
+        .ok()?;
+    let format = reader.format()?;
+    let file = archive::open(path).ok()?;
+    let profile = match format {
+        ImageFormat::Jpeg => image::codecs::jpeg::JpegDecoder::new(file)
+            .ok()?
+            .icc_profile(),
+        ImageFormat::Png => image::codecs::png::PngDecoder::new(file)
+            .ok()?
+            .icc_profile(),
+        ImageFormat::Tiff => image::codecs::tiff::TiffDecoder::new(file)
+            .ok()?
+            .icc_profile(),
+        ImageFormat::WebP => image::codecs::webp::WebPDecoder::new(file)
+            .ok()?
+            .icc_profile(),
+        _ => None,
+    }?;
+    // The profile header's data colour space field sits at bytes 16..20
+    (profile.get(16..20) == Some(b"RGB ".as_slice())).then_some(profile)
+}
+
+// Read the EXIF orientation tag (1-8). Files without EXIF, or with a bogus value, report 1 (upright).
+fn read_orientation(path: &Path) -> u32 {
+    let Ok(mut reader) = archive::open(path) else {
+        return 1;
+    };
+    exif::Reader::new()
+        .read_from_container(&mut reader)
+        .ok()
+        .and_then(|exif| {
+            exif.get_field(exif::Tag::Orientation, exif::In::PRIMARY)
+                .and_then(|field| field.value.get_uint(0))
+        })
+        .filter(|orientation| (1..=8).contains(orientation))
+        .unwrap_or(1)
+}
+
+// The EXIF DateTimeOriginal tag, checked by `parse_exif_date`
+fn read_capture_date(path: &Path) -> Option<String> {
+    let exif = exif::Reader::new()
+        .read_from_container(&mut archive::open(path).ok()?)
+        .ok()?;
+    match &exif
+        .get_field(exif::Tag::DateTimeOriginal, exif::In::PRIMARY)?
+        .value
+    {
+        exif::Value::Ascii(values) => parse_exif_date(&String::from_utf8_lossy(values.first()?)),
+        _ => None,
+    }
+}
+
+// An EXIF date is `YYYY:MM:DD HH:MM:SS`, which sorts in time order as text. Cameras whose clock
+// was never set leave it blank or zeroed, and that counts as no date.
+fn parse_exif_date(text: &str) -> Option<String> {
+    let date = text.trim().get(..19)?;
+    let shape_ok = date.char_indices().all(|(idx, c)| match idx {
+        4 | 7 | 13 | 16 => c == ':',
+        10 => c == ' ',
+        _ => c.is_ascii_digit(),
+    });
+    (shape_ok && !date.starts_with("0000")).then(|| date.to_string())
+}
+
+// The EXIF fields worth checking on a scan, as (label, value) rows. Empty when the file has no EXIF.
+fn read_metadata(path: &Path) -> Vec<(&'static str, String)> {
+    let Ok(mut reader) = archive::open(path) else {
+        return Vec::new();
+    };
+    let Ok(exif) = exif::Reader::new().read_from_container(&mut reader) else {
+        return Vec::new();
+    };
+    let text = |tag: exif::Tag| match exif.get_field(tag, exif::In::PRIMARY).map(|f| &f.value) {
+        Some(exif::Value::Ascii(values)) => values
+            .first()
+            .map(|value| String::from_utf8_lossy(value).trim().to_string())
+            .filter(|value| !value.is_empty()),
+        _ => None,
+    };
+    let number = |tag: exif::Tag| match exif.get_field(tag, exif::In::PRIMARY).map(|f| &f.value) {
+        Some(exif::Value::Rational(values)) => values.first().map(|value| value.to_f64()),
+        Some(value) => value.get_uint(0).map(|value| value as f64),
+        None => None,
+    };
+
+    let mut rows = Vec::new();
+    if let Some(date) = text(exif::Tag::DateTimeOriginal).or_else(|| text(exif::Tag::DateTime)) {
+        rows.push(("Date taken", date));
+    }
+    let camera = [text(exif::Tag::Make), text(exif::Tag::Model)]
+        .into_iter()
+        .flatten()
+        .collect::<Vec<_>>()
+        .join(" ");
+    if !camera.is_empty() {
+        rows.push(("Camera", camera));
+    }
+    if let (Some(width), Some(height)) = (
+        number(exif::Tag::PixelXDimension),
+        number(exif::Tag::PixelYDimension),
+    ) {
+        rows.push(("Resolution", format!("{}×{}", width, height)));
+    }
+    if let Some(density) = number(exif::Tag::XResolution) {
+        // ResolutionUnit 3 is centimetres; 2 (inches) is the default
+        let unit = exif
+            .get_field(exif::Tag::ResolutionUnit, exif::In::PRIMARY)
+            .and_then(|field| field.value.get_uint(0));
+        let dpi = if unit == Some(3) {
+            density * 2.54
+        } else {
+            density
+        };
+        rows.push(("DPI", format!("{:.0}", dpi)));
+    }
+    rows
+}
+
+fn apply_orientation(img: image::DynamicImage, orientation: u32) -> image::DynamicImage {
+    match orientation {
+        2 => img.fliph(),
+        3 => img.rotate180(),
+        4 => img.flipv(),
+        // Transpose: mirror across the top-left to bottom-right diagonal
+        5 => img.rotate90().fliph(),
+        6 => img.rotate90(),
+        // Transverse: mirror across the top-right to bottom-left diagonal
+        7 => img.rotate270().fliph(),
+        8 => img.rotate270(),
+        _ => img,
+    }
+}
+
+fn create_thumbnail(
+    path: &Path,
+    page: Option<usize>,
+    rotation: u16,
+    options: ThumbnailOptions,
+) -> Result<String, IrsError> {
+    // Reuse the preview from an earlier session if the source file hasn't changed since.
+    // The large-image threshold only changes how the preview is decoded, so it isn't part of the key.
+    // Brightness and contrast aren't either: the untoned preview is cached and toned on the way
+    // out, so moving the sliders doesn't store another copy of every preview.
+    let key_options = ThumbnailOptions {
+        large_image_megapixels: 0,
+        brightness: 0,
+        contrast: 0,
+        ..options
+    };
+    let cache_key = cache::key(path, (page, rotation, key_options));
+    let encode = |img: &image::DynamicImage| {
+        let mut jpg_data = Vec::new();
+        let mut encoder =
+            image::codecs::jpeg::JpegEncoder::new_with_quality(&mut jpg_data, options.quality);
+        encoder
+            .encode_image(&img.to_rgb8())
+            .map_err(IrsError::encode)?;
+        Ok::<_, IrsError>(jpg_data)
+    };
+
+    let untoned = match cache_key.and_then(cache::load) {
+        Some(jpg_data) => jpg_data,
+        None => {
+            // Previews are always re-encoded as JPEG regardless of the source format
+            let img = apply_rotation(open_for_thumbnail(path, page, options)?, rotation);
+            let thumbnail = match options.fit {
+                PreviewFit::Contain => img.thumbnail(options.size, options.size),
+                PreviewFit::Cover => img.resize_to_fill(
+                    options.size,
+                    options.size,
+                    image::imageops::FilterType::Triangle,
+                ),
+            };
+            let jpg_data = encode(&flatten_alpha(&thumbnail, options.background))?;
+            if let Some(key) = cache_key {
+                // A cache write failure only costs a regeneration next time
+                let _ = cache::store(key, &jpg_data);
+            }
+            jpg_data
+        }
+    };
+
+    if options.brightness == 0 && options.contrast == 0 {
+        return encode_to_base64(&untoned);
+    }
+    let img = image::load_from_memory_with_format(&untoned, ImageFormat::Jpeg)
+        .map_err(IrsError::decode)?;
+    encode_to_base64(&encode(&adjust_tone(
+        &img,
+        options.brightness,
+        options.contrast,
+    ))?)
+}
+
+// Decode the image a preview is made from. Up to `large_image_megapixels` that's the whole image.
+// Above it a JPEG is decoded at a reduced DCT scale (1/2 to 1/8), just large enough for the preview,
+// so the full-size pixels never exist. Other formats can only be decoded whole, so those large
+// images take turns: the preview pass holds at most one of them in memory at a time.
+fn open_for_thumbnail(
+    path: &Path,
+    page: Option<usize>,
+    options: ThumbnailOptions,
+) -> Result<image::DynamicImage, IrsError> {
+    static LARGE_DECODE: std::sync::Mutex<()> = std::sync::Mutex::new(());
+
+    let info = read_image_info(path, page, false)?;
+    let pixels = info.width as u64 * info.height as u64;
+    if pixels <= options.large_image_megapixels as u64 * 1_000_000 {
+        return open_image(path, page, options.auto_orient);
+    }
+
+    let format = image::io::Reader::new(archive::open(path)?)
+        .with_guessed_format()?
+        .format();
+    if page.is_none() && format == Some(ImageFormat::Jpeg) {
+        let file = archive::open(path)?;
+        let mut decoder = image::codecs::jpeg::JpegDecoder::new(file).map_err(IrsError::decode)?;
+        let size = options.size.min(u16::MAX as u32) as u16;
+        decoder.scale(size, size).map_err(IrsError::decode)?;
+        let img = image::DynamicImage::from_decoder(decoder).map_err(IrsError::decode)?;
+        return Ok(if options.auto_orient {
+            apply_orientation(img, read_orientation(path))
+        } else {
+            img
+        });
+    }
+
+    // A panicked decode leaves nothing behind worth protecting, so a poisoned lock is still taken
+    let _turn = LARGE_DECODE
+        .lock()
+        .unwrap_or_else(|poisoned| poisoned.into_inner());
+    open_image(path, page, options.auto_orient)
+}
+
+// Where the cuts will fall on `item`, as fractions along the axis its split mode cuts, following
+// `process_single_image`: a single cut at the card's ratio (or the detected `gutter`, which only
+// replaces the default centre), otherwise equal strips. Quadrant cuts are always the two centre
+// lines, so they aren't listed, and an image that isn't split has none.
+fn preview_cuts(item: &ImageItem, options: &OutputOptions, gutter: Option<f32>) -> Vec<f32> {
+    let (width, height) = rotated_dimensions(item);
+    if !should_split(width, height, options) {
+        return Vec::new();
+    }
+    let length = match item.split_mode.unwrap_or(options.split_mode) {
+        SplitMode::Vertical => width,
+        SplitMode::Horizontal => height,
+        SplitMode::Quadrants => return Vec::new(),
+    };
+    let parts = part_count(length, options.splits);
+    if parts == 2 {
+        let ratio = match gutter {
+            Some(gutter) if options.detect_gutter && item.split_ratio == DEFAULT_SPLIT_RATIO => {
+                gutter
+            }
+            _ => item.split_ratio,
+        };
+        return vec![ratio];
+    }
+    let strip = length / parts;
+    (1..parts)
+        .map(|idx| (idx * strip) as f32 / length as f32)
+        .collect()
+}
+
+// Run gutter detection on a card's base64 JPEG preview
+fn preview_gutter(thumbnail: &str, split_mode: SplitMode, window: u32) -> Option<f32> {
+    let img = image::load_from_memory(&decode_base64(thumbnail)?).ok()?;
+    detect_gutter(&img, split_mode, window)
+}
+
+// The part of an image a Cover preview shows along the axis `split_mode` cuts, as the fractions
+// (start, length) of the image. The longer side is cropped evenly from both ends.
+fn cover_window(width: u32, height: u32, split_mode: SplitMode) -> (f32, f32) {
+    let (along, across) = match split_mode {
+        SplitMode::Vertical | SplitMode::Quadrants => (width, height),
+        SplitMode::Horizontal => (height, width),
+    };
+    if along <= across || along == 0 {
+        return (0.0, 1.0);
+    }
+    let length = across as f32 / along as f32;
+    ((1.0 - length) / 2.0, length)
+}
+
+// Decode `item` at full resolution with the user's rotation, as base64 JPEG for the lightbox
+fn create_full_preview(
+    item: &ImageItem,
+    auto_orient: bool,
+    background: [u8; 3],
+) -> Result<String, IrsError> {
+    let img = apply_rotation(
+        open_image(&item.path, item.page, auto_orient)?,
+        item.rotation,
+    );
+    let mut jpg_data = Vec::new();
+    let mut encoder =
+        image::codecs::jpeg::JpegEncoder::new_with_quality(&mut jpg_data, LIGHTBOX_QUALITY);
+    encoder
+        .encode_image(&flatten_alpha(&img, background).to_rgb8())
+        .map_err(IrsError::encode)?;
+    encode_to_base64(&jpg_data)
+}
+
+fn apply_rotation(img: image::DynamicImage, rotation: u16) -> image::DynamicImage {
+    match rotation {
+        90 => img.rotate90(),
+        180 => img.rotate180(),
+        270 => img.rotate270(),
+        _ => img,
+    }
+}
+
+// Crop away rows and columns along the edges that are all paper, i.e. at least `threshold` bright.
+// A page that is blank throughout is returned unchanged.
+fn trim_borders(img: image::DynamicImage, threshold: u8) -> image::DynamicImage {
+    let luma = img.to_luma8();
+    let (width, height) = luma.dimensions();
+    let is_content = |count: u32, len: u32| count as f32 > len as f32 * TRIM_NOISE_FRACTION;
+    let row_has_content = |y: u32| {
+        let dark = (0..width)
+            .filter(|&x| luma.get_pixel(x, y)[0] < threshold)
+            .count();
+        is_content(dark as u32, width)
+    };
+    let column_has_content = |x: u32| {
+        let dark = (0..height)
+            .filter(|&y| luma.get_pixel(x, y)[0] < threshold)
+            .count();
+        is_content(dark as u32, height)
+    };
+
+    let (Some(top), Some(left)) = (
+        (0..height).find(|&y| row_has_content(y)),
+        (0..width).find(|&x| column_has_content(x)),
+    ) else {
+        return img;
+    };
+    let bottom = (0..height)
+        .rev()
+        .find(|&y| row_has_content(y))
+        .unwrap_or(top);
+    let right = (0..width)
+        .rev()
+        .find(|&x| column_has_content(x))
+        .unwrap_or(left);
+    img.crop_imm(left, top, right - left + 1, bottom - top + 1)
+}
+
+// Look for the book gutter in the middle `window` percent of the split direction: the darkest band
+// of columns (rows for top/bottom splits). Returns its position as a split ratio, or None when
+// nothing there is clearly darker than its surroundings.
+fn detect_gutter(img: &image::DynamicImage, split_mode: SplitMode, window: u32) -> Option<f32> {
+    let luma = img.to_luma8();
+    let (width, height) = luma.dimensions();
+    let (length, span) = match split_mode {
+        SplitMode::Vertical => (width as usize, height),
+        SplitMode::Horizontal => (height as usize, width),
+        SplitMode::Quadrants => return None,
+    };
+    if length < 3 || span == 0 {
+        return None;
+    }
+
+    // Average brightness of each column (or row) across the whole page
+    let mut sums = vec![0u64; length];
+    for (x, y, pixel) in luma.enumerate_pixels() {
+        let pos = match split_mode {
+            SplitMode::Vertical | SplitMode::Quadrants => x,
+            SplitMode::Horizontal => y,
+        };
+        sums[pos as usize] += pixel[0] as u64;
+    }
+    let profile: Vec<f32> = sums.iter().map(|&sum| sum as f32 / span as f32).collect();
+    let mean =
+        |from: usize, to: usize| profile[from..=to].iter().sum::<f32>() / (to - from + 1) as f32;
+
+    let half = length * window.min(MAX_GUTTER_WINDOW) as usize / 200;
+    let center = length / 2;
+    let (start, end) = (center.saturating_sub(half), (center + half).min(length - 1));
+    // Averaging a few neighbouring lines keeps a thin rule or scratch from passing for the gutter
+    let radius = (length / 200).max(1);
+    let (darkest, darkest_value) = (start..=end)
+        .map(|pos| {
+            let value = mean(pos.saturating_sub(radius), (pos + radius).min(length - 1));
+            (pos, value)
+        })
+        .min_by(|a, b| a.1.total_cmp(&b.1))?;
+
+    if mean(start, end) - darkest_value < GUTTER_MIN_CONTRAST {
+        return None;
+    }
+    Some((darkest as f32 / length as f32).clamp(MIN_SPLIT_RATIO, MAX_SPLIT_RATIO))
+}
+
+// Whether the card for `item` is shown for the filter text `query`: its file name contains the
+// query, ignoring case. An empty or blank query shows everything.
+fn matches_filter(item: &ImageItem, query: &str) -> bool {
+    let query = query.trim().to_lowercase();
+    query.is_empty()
+        || item
+            .path
+            .file_name()
+            .is_some_and(|name| name.to_string_lossy().to_lowercase().contains(&query))
+}
+
+// How many cards of at least `card_size` pixels fit side by side in `width`
+fn grid_columns(width: f64, card_size: u32) -> usize {
+    ((width + GRID_GAP) / (card_size as f64 + GRID_GAP))
+        .floor()
+        .max(1.0) as usize
+}
+
+// Human-readable byte count using binary units, e.g. "2.1 MB"
+fn format_file_size(bytes: u64) -> String {
+    const UNITS: [&str; 4] = ["KB", "MB", "GB", "TB"];
+
+    if bytes < 1024 {
+        return format!("{} B", bytes);
+    }
+    let mut size = bytes as f64 / 1024.0;
+    let mut unit = 0;
+    while size >= 1024.0 && unit < UNITS.len() - 1 {
+        size /= 1024.0;
+        unit += 1;
+    }
+    format!("{:.1} {}", size, UNITS[unit])
+}
+
+fn encode_to_base64(data: &[u8]) -> Result<String, IrsError> {
+    const TABLE: &[u8; 64] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+
+    // Every 3 input bytes (padded) become exactly 4 output bytes, so size the buffer once up front
+    let mut out = Vec::with_capacity(4 * data.len().div_ceil(3));
+
+    for chunk in data.chunks(3) {
+        let b1 = chunk[0];
+        let b2 = chunk.get(1).copied().unwrap_or(0);
+        let b3 = chunk.get(2).copied().unwrap_or(0);
+
+        let n = ((b1 as u32) << 16) | ((b2 as u32) << 8) | (b3 as u32);
+
+        out.push(TABLE[((n >> 18) & 63) as usize]);
+        out.push(TABLE[((n >> 12) & 63) as usize]);
+        out.push(if chunk.len() > 1 {
+            TABLE[((n >> 6) & 63) as usize]
+        } else {
+            b'='
+        });
+        out.push(if chunk.len() > 2 {
+            TABLE[(n & 63) as usize]
+        } else {
+            b'='
+        });
+    }
+
+    String::from_utf8(out).map_err(IrsError::encode)
+}
+
+// Inverse of `encode_to_base64`; None for anything that isn't padded standard base64
+fn decode_base64(text: &str) -> Option<Vec<u8>> {
+    let value = |c: u8| match c {
+        b'A'..=b'Z' => Some(c - b'A'),
+        b'a'..=b'z' => Some(c - b'a' + 26),
+        b'0'..=b'9' => Some(c - b'0' + 52),
+        b'+' => Some(62),
+        b'/' => Some(63),
+        _ => None,
+    };
+    let bytes = text.as_bytes();
+    if !bytes.len().is_multiple_of(4) {
+        return None;
+    }
+
+    let mut out = Vec::with_capacity(bytes.len() / 4 * 3);
+    for chunk in bytes.chunks(4) {
+        let padding = chunk.iter().rev().take_while(|&&c| c == b'=').count();
+        if padding > 2 {
+            return None;
+        }
+        let mut n = 0u32;
+        for &c in &chunk[..4 - padding] {
+            n = (n << 6) | value(c)? as u32;
+        }
+        n <<= 6 * padding as u32;
+        out.extend_from_slice(&n.to_be_bytes()[1..4 - padding]);
+    }
+    Some(out)
+}
+
+// Decode `item` and cut it as the output is: rotated, trimmed, greyscaled, then split into pieces
+// in reading order. Returns the prepared whole image and its pieces; no pieces when it isn't split.
+fn cut_image(
+    item: &ImageItem,
+    options: &OutputOptions,
+) -> Result<(image::DynamicImage, Vec<image::DynamicImage>), IrsError> {
+    let mut img = apply_rotation(
+        open_image(&item.path, item.page, options.auto_orient)?,
+        item.rotation,
+    );
+    if options.auto_trim {
+        img = trim_borders(img, options.trim_threshold);
+    }
+    if options.grayscale {
+        img = img.grayscale();
+    }
+    if !should_split(img.width(), img.height(), options) {
+        return Ok((img, Vec::new()));
+    }
+
+    let split_mode = item.split_mode.unwrap_or(options.split_mode);
+    // Detection only replaces the default centre cut; a divider moved by hand wins
+    let ratio = if options.detect_gutter
+        && options.splits == 2
+        && item.split_ratio == DEFAULT_SPLIT_RATIO
+    {
+        detect_gutter(&img, split_mode, options.gutter_window).unwrap_or(DEFAULT_SPLIT_RATIO)
+    } else {
+        item.split_ratio
+    };
+    let mut pieces = split_image(
+        &img,
+        split_mode,
+        options.splits,
+        ratio,
+        options.overlap,
+        options.balance_halves,
+    );
+    if pieces.len() == 2 {
+        for (piece, flip) in pieces
+            .iter_mut()
+            .zip([options.flip_left, options.flip_right])
+        {
+            *piece = match flip {
+                Flip::None => continue,
+                Flip::Horizontal => piece.fliph(),
+                Flip::Vertical => piece.flipv(),
+                Flip::Both => piece.rotate180(),
+            };
+        }
+    }
+    if split_mode == SplitMode::Vertical && options.reading_order == ReadingOrder::RightToLeft {
+        pieces.reverse();
+    }
+    Ok((img, pieces))
+}
+
+// One piece as PREVIEW SPLIT shows it
+#[derive(Clone, Debug, PartialEq)]
+struct PreviewPiece {
+    // The file it would be written to, or its page in a PDF-only run
+    name: String,
+    // Size as written, after any size limit
+    width: u32,
+    height: u32,
+    // False when the page filter leaves it out
+    written: bool,
+    // Downscaled to SPLIT_PREVIEW_SIZE, as base64 JPEG
+    jpeg_base64: String,
+}
+
+// Run `item` through the same cutting, size limit and tone adjustment as `process_single_image`,
+// in memory only, for a look at its pieces before committing a batch
+fn preview_split(
+    item: &ImageItem,
+    sequence_num: usize,
+    first_page: usize,
+    options: &OutputOptions,
+) -> Result<Vec<PreviewPiece>, IrsError> {
+    let options = &*item_options(item, options);
+    let (img, pieces) = cut_image(item, options)?;
+    let outputs = if pieces.is_empty() { vec![img] } else { pieces };
+    outputs
+        .iter()
+        .enumerate()
+        .map(|(idx, piece)| {
+            let fitted = fit_output(piece, options);
+            let piece = adjust_tone(&fitted, options.brightness, options.contrast);
+            let shown = piece.thumbnail(SPLIT_PREVIEW_SIZE, SPLIT_PREVIEW_SIZE);
+            let jpeg = encode_jpeg(&shown, LIGHTBOX_QUALITY, options.dpi, options.background)?;
+            let name = if options.pdf_export == PdfExport::Only {
+                format!("PDF page {}", first_page + idx)
+            } else {
+                output_file_name(options, item, sequence_num, idx + 1)
+            };
+            Ok(PreviewPiece {
+                name,
+                width: piece.width(),
+                height: piece.height(),
+                written: options.page_filter.keeps(first_page + idx),
+                jpeg_base64: encode_to_base64(&jpeg)?,
+            })
+        })
+        .collect()
+}
+
+fn process_single_image(
+    item: &ImageItem,
+    target: &OutputTarget,
+    subfolder: &str,
+    sequence_num: usize,
+    first_page: usize,
+    options: &OutputOptions,
+) -> Result<ImageOutput, IrsError> {
+    let options = &*item_options(item, options);
+    let (img, pieces) = cut_image(item, options)?;
+    // An RGB profile doesn't describe grey output, so it's only carried over for colour.
+    // Only the first page's profile is read, so later pages of a TIFF go without one.
+    let icc_profile = if options.grayscale || item.page.is_some_and(|page| page > 0) {
+        None
+    } else {
+        read_icc_profile(&item.path)
+    };
+    // An image that isn't split is its own only output, written without copying its pixels
+    let outputs = if pieces.is_empty() {
+        std::slice::from_ref(&img)
+    } else {
+        &pieces[..]
+    };
+
+    let comment = jpeg_comment(options, item);
+    let mut stats = WriteStats::default();
+    let mut files = Vec::new();
+    let mut write_part = |piece: &image::DynamicImage, part: usize| {
+        let file_name = output_file_name(options, item, sequence_num, part);
+        let written = target.write(subfolder, &file_name, options.overwrite_policy, |_| {
+            encode_output(piece, options, icc_profile.as_deref(), comment.as_deref())
+        })?;
+        match written {
+            Some((name, renamed)) => {
+                if renamed {
+                    stats.renamed += 1;
+                }
+                files.push(name);
+            }
+            None => stats.skipped += 1,
+        }
+        Ok::<(), IrsError>(())
+    };
+
+    // The original goes through the same encoder, numbering and overwrite policy as its pieces
+    if options.keep_original && !options.rename_only && options.pdf_export != PdfExport::Only {
+        let fitted = fit_output(&img, options);
+        write_part(
+            &adjust_tone(&fitted, options.brightness, options.contrast),
+            0,
+        )?;
+    }
+
+    let mut pages = Vec::new();
+    for (idx, piece) in outputs.iter().enumerate() {
+        if !options.page_filter.keeps(first_page + idx) {
+            continue;
+        }
+        let fitted = fit_output(piece, options);
+        let piece = &*adjust_tone(&fitted, options.brightness, options.contrast);
+        if options.pdf_export != PdfExport::Only {
+            write_part(piece, idx + 1)?;
+        }
+        if options.pdf_export != PdfExport::Off {
+            pages.push(pdf::PdfPage {
+                jpeg: encode_jpeg(piece, options.quality, options.dpi, options.background)?,
+                width: piece.width(),
+                height: piece.height(),
+                grayscale: !piece.color().has_color(),
+                dpi: options.dpi,
+            });
+        }
+    }
+
+    Ok(ImageOutput {
+        stats,
+        files,
+        pages,
+    })
+}
+
+// What a per-image select offers: the listed values, plus the image's own if a project file gave
+// it one that isn't listed, so the select still shows what the image is written with
+fn override_choices<T: Copy + PartialEq>(listed: &[T], current: Option<T>) -> Vec<T> {
+    let mut choices = listed.to_vec();
+    choices.extend(current.filter(|value| !listed.contains(value)));
+    choices
+}
+
+// The output settings `item` is written with: the batch's, with the image's own quality and DPI
+// in their place when it has them
+fn item_options<'a>(
+    item: &ImageItem,
+    options: &'a OutputOptions,
+) -> std::borrow::Cow<'a, OutputOptions> {
+    if item.quality.is_none() && item.dpi.is_none() {
+        return std::borrow::Cow::Borrowed(options);
+    }
+    std::borrow::Cow::Owned(OutputOptions {
+        quality: item.quality.unwrap_or(options.quality),
+        dpi: item.dpi.unwrap_or(options.dpi),
+        ..options.clone()
+    })
+}
+
+// Shrink `img` to fit within the size limit, if one is set. Smaller images are never enlarged.
+fn fit_output<'a>(
+    img: &'a image::DynamicImage,
+    options: &OutputOptions,
+) -> std::borrow::Cow<'a, image::DynamicImage> {
+    let max = options.max_dimension.max(1);
+    if !options.limit_size || (img.width() <= max && img.height() <= max) {
+        return std::borrow::Cow::Borrowed(img);
+    }
+    std::borrow::Cow::Owned(img.resize(max, max, image::imageops::FilterType::Lanczos3))
+}
+
+// Brighten, then change the contrast by `contrast` percent. With neither set the image is passed through.
+fn adjust_tone(
+    img: &image::DynamicImage,
+    brightness: i32,
+    contrast: i32,
+) -> std::borrow::Cow<'_, image::DynamicImage> {
+    if brightness == 0 && contrast == 0 {
+        return std::borrow::Cow::Borrowed(img);
+    }
+    let mut adjusted = img.brighten(brightness);
+    if contrast != 0 {
+        adjusted = adjusted.adjust_contrast(contrast as f32);
+    }
+    std::borrow::Cow::Owned(adjusted)
+}
+
+// Lay a transparent image over a solid `background`. A grey image on a grey background stays
+// single-channel. Images without alpha are passed through.
+fn flatten_alpha(
+    img: &image::DynamicImage,
+    background: [u8; 3],
+) -> std::borrow::Cow<'_, image::DynamicImage> {
+    if !img.color().has_alpha() {
+        return std::borrow::Cow::Borrowed(img);
+    }
+    let rgba = img.to_rgba8();
+    let flattened = image::RgbImage::from_fn(img.width(), img.height(), |x, y| {
+        let pixel = rgba[(x, y)];
+        let alpha = pixel[3] as u32;
+        image::Rgb(std::array::from_fn(|c| {
+            ((pixel[c] as u32 * alpha + background[c] as u32 * (255 - alpha) + 127) / 255) as u8
+        }))
+    });
+    let grey = background[0] == background[1] && background[1] == background[2];
+    std::borrow::Cow::Owned(if grey && !img.color().has_color() {
+        image::DynamicImage::ImageLuma8(image::DynamicImage::ImageRgb8(flattened).to_luma8())
+    } else {
+        image::DynamicImage::ImageRgb8(flattened)
+    })
+}
+
+// `#rrggbb`, as used by colour inputs
+fn hex_color(color: [u8; 3]) -> String {
+    format!("#{:02x}{:02x}{:02x}", color[0], color[1], color[2])
+}
+
+fn parse_hex_color(text: &str) -> Option<[u8; 3]> {
+    let hex = text.strip_prefix('#')?;
+    if hex.len() != 6 {
+        return None;
+    }
+    let channel = |idx: usize| u8::from_str_radix(hex.get(idx..idx + 2)?, 16).ok();
+    Some([channel(0)?, channel(2)?, channel(4)?])
+}
+
+// Work out every file a run would write without decoding or touching any image data.
+// Numbering and collision handling match `process_images_sync` and `process_single_image`.
+fn plan_output_names(
+    images: &[ImageItem],
+    spl_folder: &Path,
+    options: &OutputOptions,
+) -> Vec<String> {
+    let mut pdf_page = 1;
+    let mut plan: Vec<String> = images
+        .iter()
+        .zip(first_pages(images, options))
+        .zip(output_slots(images, options))
+        .map(|((item, first_page), (subfolder, sequence_num))| {
+            let folder = slot_folder(spl_folder, &subfolder);
+            let parts = (1..=planned_parts(item, options))
+                .filter(|part| options.page_filter.keeps(first_page + part - 1))
+                .collect::<Vec<_>>();
+            let file_name = item_name(item);
+            if options.pdf_export == PdfExport::Only {
+                if parts.is_empty() {
+                    return format!("{} → no pages", file_name);
+                }
+                let pages = format!("PDF pages {}–{}", pdf_page, pdf_page + parts.len() - 1);
+                pdf_page += parts.len();
+                return format!("{} → {}", file_name, pages);
+            }
+            let original = (first_part(options) == 0).then_some(0);
+            if original.is_none() && parts.is_empty() {
+                return format!("{} → nothing", file_name);
+            }
+            let outputs = original
+                .into_iter()
+                .chain(parts)
+                .map(|part| {
+                    let file_name = output_file_name(options, item, sequence_num, part);
+                    let path = folder.join(&file_name);
+                    let name = slot_file_name(&subfolder, &file_name);
+                    // A ZIP is written afresh, so nothing in it exists yet
+                    let resolved = if options.zip_output {
+                        Some((path, false))
+                    } else {
+                        resolve_output_path(path, options.overwrite_policy)
+                    };
+                    match resolved {
+                        Some((path, true)) => format!(
+                            "{} (exists, writes {})",
+                            name,
+                            path.file_name().unwrap_or_default().to_string_lossy()
+                        ),
+                        Some((_, false)) => name,
+                        None => format!("{} (exists, skipped)", name),
+                    }
+                })
+                .collect::<Vec<_>>()
+                .join(", ");
+            format!("{} → {}", file_name, outputs)
+        })
+        .collect();
+
+    if options.pdf_export != PdfExport::Off && !images.is_empty() {
+        plan.push(format!("All pages → {}", pdf_file_name(options)));
+    }
+    if options.zip_output && !images.is_empty() {
+        plan.push(format!("All files → {}", output_archive_name(options)));
+    }
+    plan
+}
+
+// Where each image's first piece falls among all the pages of the run, counting from 1
+fn first_pages(images: &[ImageItem], options: &OutputOptions) -> Vec<usize> {
+    let mut next_page = 1;
+    images
+        .iter()
+        .map(|item| {
+            let first_page = next_page;
+            next_page += planned_parts(item, options);
+            first_page
+        })
+        .collect()
+}
+
+// Whether an image of this (rotated) size gets cut at all under `options`
+fn should_split(width: u32, height: u32, options: &OutputOptions) -> bool {
+    let portrait = width <= height;
+    !(options.rename_only || options.landscape_only && portrait)
+}
+
+// Output files are numbered from 1, or from 0 when the unsplit original is kept too
+fn first_part(options: &OutputOptions) -> usize {
+    if options.keep_original && !options.rename_only {
+        0
+    } else {
+        1
+    }
+}
+
+// How many pieces `item` will be cut into, judged from its size after rotation and its own split mode
+fn planned_parts(item: &ImageItem, options: &OutputOptions) -> usize {
+    let (width, height) = rotated_dimensions(item);
+    if !should_split(width, height, options) {
+        return 1;
+    }
+    let parts = match item.split_mode.unwrap_or(options.split_mode) {
+        SplitMode::Vertical => part_count(width, options.splits),
+        SplitMode::Horizontal => part_count(height, options.splits),
+        SplitMode::Quadrants => part_count(width, 2) * part_count(height, 2),
+    };
+    parts as usize
+}
+
+// Every output name the run would write, in order; the first one planned twice is returned
+// so nothing is written when two pieces would land on the same file.
+fn find_duplicate_output(images: &[ImageItem], options: &OutputOptions) -> Option<String> {
+    if options.pdf_export == PdfExport::Only {
+        return None;
+    }
+    let mut planned = HashSet::new();
+    for (item, (subfolder, sequence_num)) in images.iter().zip(output_slots(images, options)) {
+        for part in first_part(options)..=planned_parts(item, options) {
+            let name = slot_file_name(
+                &subfolder,
+                &output_file_name(options, item, sequence_num, part),
+            );
+            // Compared ignoring case, since Windows and macOS file systems are case-insensitive
+            if !planned.insert(name.to_lowercase()) {
+                return Some(name);
+            }
+        }
+    }
+    None
+}
+
+// Subfolder and sequence number of each image's output, in list order. Flat output has no
+// subfolder. Mirroring names each one after the image's source folder, adding `_2` etc. when
+// different folders share a name, and numbers across the run or afresh in each subfolder.
+fn output_slots(images: &[ImageItem], options: &OutputOptions) -> Vec<(String, usize)> {
+    let mut subfolders: HashMap<&Path, String> = HashMap::new();
+    let mut taken = HashSet::new();
+    let mut counts: HashMap<String, usize> = HashMap::new();
+    images
+        .iter()
+        .enumerate()
+        .map(|(idx, item)| {
+            if options.folder_structure == FolderStructure::Flat {
+                return (String::new(), options.start_index + idx);
+            }
+            let parent = item.path.parent().unwrap_or(Path::new(""));
+            let subfolder = subfolders
+                .entry(parent)
+                .or_insert_with(|| {
+                    // A file at the root of a drive has no folder name to borrow
+                    let base = parent
+                        .file_name()
+                        .map(|name| name.to_string_lossy().to_string())
+                        .unwrap_or_else(|| "images".to_string());
+                    let mut name = base.clone();
+                    // Folder names clash ignoring case, as in `find_duplicate_output`
+                    for n in 2.. {
+                        if taken.insert(name.to_lowercase()) {
+                            break;
+                        }
+                        name = format!("{}_{}", base, n);
+                    }
+                    name
+                })
+                .clone();
+            let sequence_num = if options.number_per_folder {
+                let count = counts.entry(subfolder.clone()).or_default();
+                *count += 1;
+                options.start_index + *count - 1
+            } else {
+                options.start_index + idx
+            };
+            (subfolder, sequence_num)
+        })
+        .collect()
+}
+
+// Folder an image's files are written to: the run's folder, or its subfolder when mirroring
+fn slot_folder(spl_folder: &Path, subfolder: &str) -> PathBuf {
+    if subfolder.is_empty() {
+        spl_folder.to_path_buf()
+    } else {
+        spl_folder.join(subfolder)
+    }
+}
+
+// An output file as named in plans and errors, e.g. `vol1/001_1.jpg` when mirroring
+fn slot_file_name(subfolder: &str, file_name: &str) -> String {
+    if subfolder.is_empty() {
+        file_name.to_string()
+    } else {
+        format!("{}/{}", subfolder, file_name)
+    }
+}
+
+// Orders offered by the SORT buttons in the controls
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum SortOrder {
+    // Natural file name order, numbers in order
+    Name,
+    Modified,
+    // EXIF capture date, falling back to the modification time
+    Captured,
+}
+
+// `images` reordered by `order`; ties keep their current order
+fn sorted_images(images: &[ImageItem], order: SortOrder) -> Vec<ImageItem> {
+    let mut sorted = images.to_vec();
+    match order {
+        SortOrder::Name => sorted.sort_by(|a, b| scan::natural_cmp(&item_name(a), &item_name(b))),
+        SortOrder::Modified => sorted.sort_by_key(|img| img.modified),
+        // Undated images go last rather than first
+        SortOrder::Captured => sorted.sort_by_key(|img| {
+            let date = capture_date(img);
+            (date.is_none(), date)
+        }),
+    }
+    sorted
+}
+
+// When an image was taken, in the EXIF date format so it compares with EXIF dates: its capture
+// date, or its modification time in local time when it has none
+fn capture_date(item: &ImageItem) -> Option<String> {
+    item.captured.clone().or_else(|| {
+        item.modified.map(|modified| {
+            chrono::DateTime::<chrono::Local>::from(modified)
+                .format("%Y:%m:%d %H:%M:%S")
+                .to_string()
+        })
+    })
+}
+
+// Pixel size of an item once its quarter-turn rotation is applied
+fn rotated_dimensions(item: &ImageItem) -> (u32, u32) {
+    if item.rotation % 180 == 90 {
+        (item.height, item.width)
+    } else {
+        (item.width, item.height)
+    }
+}
+
+// A strip must be at least one pixel wide, so tiny images get fewer parts than requested
+fn part_count(length: u32, parts: u32) -> u32 {
+    parts.clamp(1, length.max(1))
+}
+
+// Cut an image into `parts` strips, returned in reading order: left to right, top to bottom.
+// A two-way split cuts at `ratio`; otherwise strips are equal and the last absorbs the remainder pixels.
+// Each strip then grows by `overlap` pixels across every cut, stopping at the image edges.
+// Quadrants halve the top and bottom strips again; on odd sizes the bottom and right pieces get the extra pixel.
+fn split_image(
+    img: &image::DynamicImage,
+    split_mode: SplitMode,
+    parts: u32,
+    ratio: f32,
+    overlap: u32,
+    balance: bool,
+) -> Vec<image::DynamicImage> {
+    let (width, height) = img.dimensions();
+    let length = match split_mode {
+        SplitMode::Vertical => width,
+        SplitMode::Horizontal => height,
+        SplitMode::Quadrants => {
+            return split_image(
+                img,
+                SplitMode::Horizontal,
+                2,
+                DEFAULT_SPLIT_RATIO,
+                overlap,
+                balance,
+            )
+            .iter()
+            .flat_map(|row| {
+                split_image(
+                    row,
+                    SplitMode::Vertical,
+                    2,
+                    DEFAULT_SPLIT_RATIO,
+                    overlap,
+                    balance,
+                )
+            })
+            .collect();
+        }
+    };
+    let parts = part_count(length, parts);
+
+    // Strip boundaries from the first edge to the last
+    let bounds: Vec<u32> = if parts == 2 {
+        let cut = ((length as f64 * ratio as f64) as u32).clamp(1, length - 1);
+        vec![0, cut, length]
+    } else {
+        let strip = length / parts;
+        (0..parts)
+            .map(|idx| idx * strip)
+            .chain(std::iter::once(length))
+            .collect()
+    };
+
+    // With `balance`, an odd length cut exactly in the middle leaves out index length / 2,
+    // so the second half starts one pixel later and comes out as long as the first
+    let dropped = u32::from(
+        balance
+            && parts == 2
+            && ratio == DEFAULT_SPLIT_RATIO
+            && length > 2
+            && !length.is_multiple_of(2),
+    );
+
+    bounds
+        .windows(2)
+        .enumerate()
+        .map(|(idx, edges)| {
+            let first = if idx == 1 {
+                edges[0] + dropped
+            } else {
+                edges[0]
+            };
+            let start = first.saturating_sub(overlap);
+            let size = edges[1].saturating_add(overlap).min(length) - start;
+            match split_mode {
+                SplitMode::Vertical | SplitMode::Quadrants => img.crop_imm(start, 0, size, height),
+                SplitMode::Horizontal => img.crop_imm(0, start, width, size),
+            }
+        })
+        .collect()
+}
+
+// Where a run writes its files: into the output folder, or as entries of one ZIP archive that
+// the worker threads take turns adding to
+enum OutputTarget {
+    Folder(PathBuf),
+    Archive(std::sync::Mutex<zip::ZipWriter<std::io::BufWriter<std::fs::File>>>),
+}
+
+impl OutputTarget {
+    // Write one file, inside `subfolder` when source folders are mirrored, under the overwrite
+    // policy. `contents` makes the bytes once the file is known to be wanted, given the name it
+    // ends up with. Returns that name and whether it was changed to keep an existing file, or
+    // None when the existing file was left alone. An archive is always new, so its entries keep
+    // their planned names.
+    fn write(
+        &self,
+        subfolder: &str,
+        file_name: &str,
+        policy: OverwritePolicy,
+        contents: impl FnOnce(&str) -> Result<Vec<u8>, IrsError>,
+    ) -> Result<Option<(String, bool)>, IrsError> {
+        use std::io::Write;
+
+        match self {
+            OutputTarget::Folder(spl_folder) => {
+                let path = slot_folder(spl_folder, subfolder).join(file_name);
+                let Some((path, renamed)) = resolve_output_path(path, policy) else {
+                    return Ok(None);
+                };
+                let name = path.file_name().unwrap_or_default().to_string_lossy();
+                std::fs::write(&path, contents(&name)?)?;
+                Ok(Some((name.to_string(), renamed)))
+            }
+            OutputTarget::Archive(zip) => {
+                // Encoded before taking the lock, so workers only wait on each other for the copy
+                let bytes = contents(file_name)?;
+                let mut zip = zip.lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+                // JPEG and PNG are compressed already, and deflating them again gains nothing
+                let options = zip::write::SimpleFileOptions::default()
+                    .compression_method(zip::CompressionMethod::Stored);
+                zip.start_file(slot_file_name(subfolder, file_name), options)
+                    .map_err(std::io::Error::other)?;
+                zip.write_all(&bytes)?;
+                Ok(Some((file_name.to_string(), false)))
+            }
+        }
+    }
+}
+
+// Decide where an output file should be written under the overwrite policy.
+// Returns None when the file should be skipped, otherwise the path and whether it was renamed.
+fn resolve_output_path(path: PathBuf, policy: OverwritePolicy) -> Option<(PathBuf, bool)> {
+    if !path.exists() {
+        return Some((path, false));
+    }
+
+    match policy {
+        OverwritePolicy::Overwrite => Some((path, false)),
+        OverwritePolicy::Skip => None,
+        OverwritePolicy::RenameWithSuffix => {
+            let stem = path
+                .file_stem()
+                .unwrap_or_default()
+                .to_string_lossy()
+                .to_string();
+            let extension = path
+                .extension()
+                .map(|ext| format!(".{}", ext.to_string_lossy()))
+                .unwrap_or_default();
+            (2..)
+                .map(|version| path.with_file_name(format!("{}_v{}{}", stem, version, extension)))
+                .find(|candidate| !candidate.exists())
+                .map(|candidate| (candidate, true))
+        }
+    }
+}
+
+// Encode `img` in the output format with the output's DPI; JPEGs also get the quality,
+// background, ICC profile and comment
+fn encode_output(
+    img: &image::DynamicImage,
+    options: &OutputOptions,
+    icc_profile: Option<&[u8]>,
+    comment: Option<&str>,
+) -> Result<Vec<u8>, IrsError> {
+    if options.format == OutputFormat::Png {
+        return encode_png_with_dpi(img, options.dpi);
+    }
+
+    let mut jpg_buf = encode_jpeg(img, options.quality, options.dpi, options.background)?;
+    if let Some(profile) = icc_profile {
+        set_jpeg_icc_profile(&mut jpg_buf, profile)?;
+    }
+    if let Some(comment) = comment {
+        set_jpeg_comment(&mut jpg_buf, comment)?;
+    }
+    Ok(jpg_buf)
+}
+
+// JPEG-encode an image with its DPI recorded in the JFIF header.
+// Grey images keep a single channel; everything else is written as RGB.
+// Transparent areas are flattened onto `background` first.
+fn encode_jpeg(
+    img: &image::DynamicImage,
+    quality: u8,
+    dpi: u16,
+    background: [u8; 3],
+) -> Result<Vec<u8>, IrsError> {
+    let img = &*flatten_alpha(img, background);
+    // Encode image into an in-memory JPEG buffer first
+    let mut jpg_buf: Vec<u8> = Vec::new();
+    {
+        let mut encoder = image::codecs::jpeg::JpegEncoder::new_with_quality(
+            &mut jpg_buf,
+            clamp_quality(quality as i64),
+        );
+        let encoded = if img.color().has_color() {
+            encoder.encode_image(&img.to_rgb8())
+        } else {
+            encoder.encode_image(&img.to_luma8())
+        };
+        encoded.map_err(IrsError::encode)?;
+    }
+
+    // Ensure JFIF APP0 segment sets DPI (units = inch, X/Y density)
+    set_jpeg_dpi(&mut jpg_buf, dpi)?;
+    Ok(jpg_buf)
+}
+
+fn encode_png_with_dpi(img: &image::DynamicImage, dpi: u16) -> Result<Vec<u8>, IrsError> {
+    use image::{ColorType, ImageEncoder};
+
+    // PNG stores 8 and 16 bit gray/RGB with or without alpha; anything else (float TIFFs) becomes RGBA8
+    let converted;
+    let img = match img.color() {
+        ColorType::L8
+        | ColorType::La8
+        | ColorType::Rgb8
+        | ColorType::Rgba8
+        | ColorType::L16
+        | ColorType::La16
+        | ColorType::Rgb16
+        | ColorType::Rgba16 => img,
+        _ => {
+            converted = image::DynamicImage::ImageRgba8(img.to_rgba8());
+            &converted
+        }
+    };
+
+    let mut png_buf: Vec<u8> = Vec::new();
+    image::codecs::png::PngEncoder::new(&mut png_buf)
+        .write_image(img.as_bytes(), img.width(), img.height(), img.color())
+        .map_err(IrsError::encode)?;
+
+    set_png_dpi(&mut png_buf, dpi)?;
+    Ok(png_buf)
+}
+
+// Insert a pHYs chunk right after IHDR. PNG measures density per metre, so the DPI is converted.
+fn set_png_dpi(buf: &mut Vec<u8>, dpi: u16) -> Result<(), IrsError> {
+    const SIGNATURE: &[u8] = b"\x89PNG\r\n\x1a\n";
+    // Signature (8) + IHDR length (4) + type (4) + data (13) + CRC (4)
+    const IHDR_END: usize = 8 + 4 + 4 + 13 + 4;
+
+    if buf.len() < IHDR_END || !buf.starts_with(SIGNATURE) || &buf[12..16] != b"IHDR" {
+        return Err(IrsError::encode("Not a valid PNG"));
+    }
+
+    let pixels_per_metre = (dpi as f64 / 0.0254).round() as u32;
+    let mut chunk_data = Vec::with_capacity(4 + 9);
+    chunk_data.extend_from_slice(b"pHYs");
+    chunk_data.extend_from_slice(&pixels_per_metre.to_be_bytes());
+    chunk_data.extend_from_slice(&pixels_per_metre.to_be_bytes());
+    chunk_data.push(1); // unit = metre
+
+    let mut chunk = Vec::with_capacity(4 + chunk_data.len() + 4);
+    chunk.extend_from_slice(&9u32.to_be_bytes());
+    chunk.extend_from_slice(&chunk_data);
+    chunk.extend_from_slice(&crc32(&chunk_data).to_be_bytes());
+
+    buf.splice(IHDR_END..IHDR_END, chunk);
+    Ok(())
+}
+
+// CRC-32 (ISO 3309) over the chunk type and data, as required for every PNG chunk
+fn crc32(data: &[u8]) -> u32 {
+    let mut crc = 0xFFFF_FFFFu32;
+    for &byte in data {
+        crc ^= byte as u32;
+        for _ in 0..8 {
+            crc = if crc & 1 != 0 {
+                (crc >> 1) ^ 0xEDB8_8320
+            } else {
+                crc >> 1
+            };
+        }
+    }
+    !crc
+}
+
+// Find the JFIF APP0 segment and set its units and X/Y density. The density sits at fixed offsets
+// in the JFIF header, ahead of any embedded thumbnail, so an APP0 of any length is patched in
+// place once its header checks out. A malformed JFIF APP0 is replaced, thumbnail and all, and a
+// missing one is inserted after SOI.
+fn set_jpeg_dpi(buf: &mut Vec<u8>, dpi: u16) -> Result<(), IrsError> {
+    // Length field, identifier, version, units, X/Y density and thumbnail size
+    const JFIF_HEADER_LEN: usize = 16;
+    // Validate JPEG SOI
+    if buf.len() < 4 || buf[0] != 0xFF || buf[1] != 0xD8 {
+        return Err(IrsError::InvalidJpeg("Not a valid JPEG"));
+    }
+    let [dpi_hi, dpi_lo] = dpi.to_be_bytes();
+
+    // Walk segments starting after SOI, up to Start of Scan (0xDA) where the image data begins
+    let mut replaced = 2..2;
+    let mut i = 2usize;
+    while i + 4 <= buf.len() && buf[i] == 0xFF && buf[i + 1] != 0xDA {
+        let len = ((buf[i + 2] as usize) << 8) | (buf[i + 3] as usize);
+        let end = i + 2 + len;
+        if len < 2 || end > buf.len() {
+            return Err(IrsError::InvalidJpeg("Truncated JPEG segment"));
+        }
+        // APP0 with the "JFIF\0" identifier; a JFXX extension APP0 is passed over
+        if buf[i + 1] == 0xE0 && buf[i + 4..end].starts_with(b"JFIF\0") {
+            // Version 1.x, with units of none (aspect ratio only), inches or centimetres
+            let (version, units) = (buf.get(i + 9), buf.get(i + 11));
+            if len >= JFIF_HEADER_LEN && version == Some(&1) && units.is_some_and(|&u| u <= 2) {
+                buf[i + 11] = 1; // dots per inch
+                buf[i + 12..i + 16].copy_from_slice(&[dpi_hi, dpi_lo, dpi_hi, dpi_lo]);
+                return Ok(());
+            }
+            replaced = i..end;
+            break;
+        }
+        i = end;
+    }
+
+    let app0 = [
+        0xFF, 0xE0, // APP0 marker
+        0x00, 0x10, // length 16
+        b'J', b'F', b'I', b'F', 0x00, // identifier
+        0x01, 0x02, // version 1.2
+        0x01, // units = dots per inch
+        dpi_hi, dpi_lo, // X density
+        dpi_hi, dpi_lo, // Y density
+        0x00, 0x00, // no thumbnail
+    ];
+    buf.splice(replaced, app0);
+    Ok(())
+}
+
+// Embed an ICC profile as APP2 `ICC_PROFILE` segments right after the JFIF APP0.
+// A segment holds at most 65519 bytes of profile, so larger profiles are split and numbered from 1.
+fn set_jpeg_icc_profile(buf: &mut Vec<u8>, profile: &[u8]) -> Result<(), IrsError> {
+    const MAX_CHUNK: usize = 65_535 - 2 - 14; // length field and header take their share
+    if buf.len() < 4 || buf[0] != 0xFF || buf[1] != 0xD8 {
+        return Err(IrsError::InvalidJpeg("Not a valid JPEG"));
+    }
+
+    let chunks: Vec<&[u8]> = profile.chunks(MAX_CHUNK).collect();
+    if chunks.len() > 255 {
+        return Err(IrsError::encode("ICC profile is too large to embed"));
+    }
+
+    // Readers expect JFIF APP0 to come first, so the profile goes after it when present
+    let mut insert_at = 2;
+    if buf[2] == 0xFF && buf[3] == 0xE0 && buf.len() >= 6 {
+        insert_at += 2 + (((buf[4] as usize) << 8) | buf[5] as usize);
+    }
+
+    let mut segments = Vec::with_capacity(profile.len() + chunks.len() * 18);
+    for (idx, chunk) in chunks.iter().enumerate() {
+        let len = 2 + 14 + chunk.len();
+        segments.extend_from_slice(&[0xFF, 0xE2, (len >> 8) as u8, (len & 0xFF) as u8]);
+        segments.extend_from_slice(b"ICC_PROFILE\0");
+        segments.push(idx as u8 + 1); // sequence number
+        segments.push(chunks.len() as u8); // total segments
+        segments.extend_from_slice(chunk);
+    }
+    buf.splice(insert_at..insert_at, segments);
+
+    Ok(())
+}
+
+// The text of the COM segment written into `item`'s JPEG output, e.g.
+// `Processed by IRS; source: scan012.tif`, or None when comments are off
+fn jpeg_comment(options: &OutputOptions, item: &ImageItem) -> Option<String> {
+    if !options.embed_comment {
+        return None;
+    }
+    let text = options.comment.trim();
+    Some(if text.is_empty() {
+        format!("Source: {}", item_name(item))
+    } else {
+        format!("{}; source: {}", text, item_name(item))
+    })
+}
+
+// Insert a COM (0xFE) segment after the leading APPn segments (JFIF, ICC profile), where readers
+// look for it. A segment holds at most 65533 bytes, so longer text is cut at a character boundary.
+fn set_jpeg_comment(buf: &mut Vec<u8>, comment: &str) -> Result<(), IrsError> {
+    const MAX_TEXT: usize = 65_535 - 2;
+    if buf.len() < 4 || buf[0] != 0xFF || buf[1] != 0xD8 {
+        return Err(IrsError::InvalidJpeg("Not a valid JPEG"));
+    }
+
+    let mut insert_at = 2;
+    while insert_at + 4 <= buf.len()
+        && buf[insert_at] == 0xFF
+        && (0xE0..=0xEF).contains(&buf[insert_at + 1])
+    {
+        insert_at += 2 + (((buf[insert_at + 2] as usize) << 8) | buf[insert_at + 3] as usize);
+    }
+    if insert_at > buf.len() {
+        return Err(IrsError::InvalidJpeg("Truncated JPEG segment"));
+    }
+
+    let mut end = comment.len().min(MAX_TEXT);
+    while !comment.is_char_boundary(end) {
+        end -= 1;
+    }
+    let text = &comment.as_bytes()[..end];
+    let len = 2 + text.len();
+    let mut segment = Vec::with_capacity(2 + len);
+    segment.extend_from_slice(&[0xFF, 0xFE, (len >> 8) as u8, (len & 0xFF) as u8]);
+    segment.extend_from_slice(text);
+    buf.splice(insert_at..insert_at, segment);
+    Ok(())
+}
+
+// `only` limits the run to those item ids; every image still keeps the number and page position it
+// has in the full list, so a retry writes exactly the files the first run should have.
+fn process_images_sync(
+    images: Vec<ImageItem>,
+    only: Option<HashSet<usize>>,
+    save_folder: PathBuf,
+    options: OutputOptions,
+    cancel: std::sync::Arc<AtomicBool>,
+    on_progress: impl Fn(usize, usize, LogLine),
+) -> Result<ProcessSummary, IrsError> {
+    // Synchronous version of the threaded processing. Returns a summary of the run or the error
+    // that stopped it.
+    validate_subfolder_name(&options.subfolder).map_err(IrsError::Refused)?;
+    validate_name_template(&options.name_template).map_err(IrsError::Refused)?;
+    if let Some(name) = find_duplicate_output(&images, &options) {
+        return Err(IrsError::Refused(format!(
+            "Duplicate output name: {}",
+            name
+        )));
+    }
+    // Checked up front so a moved source stops the run before anything is written,
+    // rather than turning up as a decode failure halfway through
+    if let Some(missing) = find_missing_sources(&images, only.as_ref()) {
+        return Err(IrsError::Refused(missing.message()));
+    }
+    let mut stats = WriteStats::default();
+    // The folder, or the archive standing in for it, as reported back in the summary
+    let (output_path, target) = if options.zip_output {
+        let path = save_folder.join(output_archive_name(&options));
+        let Some((path, renamed)) = resolve_output_path(path, options.overwrite_policy) else {
+            return Err(IrsError::Refused(format!(
+                "{} already exists; choose OVERWRITE or KEEP BOTH to write it anyway",
+                output_archive_name(&options)
+            )));
+        };
+        if renamed {
+            stats.renamed += 1;
+        }
+        std::fs::create_dir_all(&save_folder).map_err(|source| IrsError::OutputDir {
+            path: save_folder.clone(),
+            source,
+        })?;
+        let file = std::fs::File::create(&path).map_err(|source| IrsError::OutputDir {
+            path: path.clone(),
+            source,
+        })?;
+        let zip = zip::ZipWriter::new(std::io::BufWriter::new(file));
+        (path, OutputTarget::Archive(std::sync::Mutex::new(zip)))
+    } else {
+        let spl_folder = save_folder.join(output_subfolder_name(&options));
+        std::fs::create_dir_all(&spl_folder).map_err(|source| IrsError::OutputDir {
+            path: spl_folder.clone(),
+            source,
+        })?;
+        (spl_folder.clone(), OutputTarget::Folder(spl_folder))
+    };
+
+    // Each job is an image with its subfolder, sequence number and the page position of its
+    // first piece
+    let first_pages = first_pages(&images, &options);
+    let slots = output_slots(&images, &options);
+    let jobs: Vec<(ImageItem, String, usize, usize)> = images
+        .into_iter()
+        .zip(slots)
+        .zip(first_pages)
+        .filter(|((item, _), _)| only.as_ref().is_none_or(|ids| ids.contains(&item.id)))
+        .map(|((item, (subfolder, sequence_num)), first_page)| {
+            (item, subfolder, sequence_num, first_page)
+        })
+        .collect();
+    // A PDF-only run writes nothing into the subfolders, and an archive needs none made
+    if let (OutputTarget::Folder(spl_folder), false) =
+        (&target, options.pdf_export == PdfExport::Only)
+    {
+        let subfolders: HashSet<&str> = jobs
+            .iter()
+            .map(|(_, subfolder, _, _)| subfolder.as_str())
+            .filter(|subfolder| !subfolder.is_empty())
+            .collect();
+        for subfolder in subfolders {
+            let path = spl_folder.join(subfolder);
+            std::fs::create_dir_all(&path)
+                .map_err(|source| IrsError::OutputDir { path, source })?;
+        }
+    }
+
+    let (tx, rx) = std::sync::mpsc::channel();
+    let target_arc = std::sync::Arc::new(target);
+    let options_arc = std::sync::Arc::new(options);
+    let mut handles = Vec::new();
+
+    let total_images = jobs.len();
+    let chunk_size = chunk_size(total_images, worker_count(options_arc.threads));
+
+    for chunk in jobs.chunks(chunk_size) {
+        let chunk_clone = chunk.to_vec();
+        let tx = tx.clone();
+        let target = std::sync::Arc::clone(&target_arc);
+        let options = std::sync::Arc::clone(&options_arc);
+        let cancel = std::sync::Arc::clone(&cancel);
+
+        let handle = std::thread::spawn(move || {
+            for (item, subfolder, current_num, first_page) in &chunk_clone {
+                // Images already being written finish; the rest of the chunk is left alone
+                if cancel.load(Ordering::Relaxed) {
+                    break;
+                }
+                let file_name = item_name(item);
+                let result = process_single_image(
+                    item,
+                    &target,
+                    subfolder,
+                    *current_num,
+                    *first_page,
+                    &options,
+                );
+                let _ = tx.send((item.id, file_name, *current_num, result));
+            }
+        });
+
+        handles.push(handle);
+    }
+
+    drop(tx);
+
+    // Collect results (this will block until all senders are dropped), reporting progress as each image finishes
+    let mut completed = 0usize;
+    let mut processed = 0usize;
+    let mut failed: Vec<String> = Vec::new();
+    let mut failed_ids: Vec<usize> = Vec::new();
+    // PDF pages arrive out of order from the workers; they're sorted by page position once all
+    // are in. Each image is keyed by its subfolder and number, unique even when numbering restarts.
+    let mut pdf_pages: Vec<(usize, (String, usize), Vec<pdf::PdfPage>)> = Vec::new();
+    let mut manifest_rows: Vec<manifest::ManifestRow> = Vec::new();
+    let items: HashMap<usize, (&ImageItem, &String, usize)> = jobs
+        .iter()
+        .map(|(item, subfolder, _, first_page)| (item.id, (item, subfolder, *first_page)))
+        .collect();
+    for (id, file_name, sequence_num, result) in rx.iter() {
+        completed += 1;
+        let line = match &result {
+            Ok(_) => LogLine {
+                success: true,
+                text: format!("✓ {} (#{})", file_name, sequence_num),
+            },
+            Err(e) => LogLine {
+                success: false,
+                text: format!("✗ {}: {}", file_name, e),
+            },
+        };
+        on_progress(completed, total_images, line);
+        let (item, subfolder, first_page) = items[&id];
+        if options_arc.write_manifest {
+            let (width, height) = rotated_dimensions(item);
+            manifest_rows.push(manifest::ManifestRow {
+                source: item_source(item),
+                folder: subfolder.clone(),
+                outputs: result
+                    .as_ref()
+                    .map(|output| output.files.clone())
+                    .unwrap_or_default(),
+                sequence: sequence_num,
+                width,
+                height,
+                error: result.as_ref().err().map(ToString::to_string),
+            });
+        }
+        match result {
+            Ok(output) => {
+                processed += 1;
+                stats.skipped += output.stats.skipped;
+                stats.renamed += output.stats.renamed;
+                pdf_pages.push((first_page, (subfolder.clone(), sequence_num), output.pages));
+            }
+            Err(_) => {
+                failed.push(file_name);
+                failed_ids.push(id);
+            }
+        }
+    }
+
+    // Join threads
+    for handle in handles {
+        let _ = handle.join();
+    }
+
+    let cancelled = completed < total_images;
+    let mut pdf_name = None;
+    // A cancelled run would leave a PDF with missing pages, so none is written
+    if options_arc.pdf_export != PdfExport::Off && !cancelled && processed > 0 {
+        pdf_pages.sort_by_key(|(first_page, _, _)| *first_page);
+        // The manifest lists where each image's pages land, counted from 1
+        let mut pdf_ranges: HashMap<(String, usize), (usize, usize)> = HashMap::new();
+        let mut next_page = 1;
+        for (_, key, pages) in &pdf_pages {
+            if !pages.is_empty() {
+                pdf_ranges.insert(key.clone(), (next_page, next_page + pages.len() - 1));
+                next_page += pages.len();
+            }
+        }
+        let pages: Vec<pdf::PdfPage> = pdf_pages
+            .into_iter()
+            .flat_map(|(_, _, pages)| pages)
+            .collect();
+        // The page filter can leave nothing to put in it
+        if !pages.is_empty() {
+            let written = target_arc.write(
+                "",
+                &pdf_file_name(&options_arc),
+                options_arc.overwrite_policy,
+                |name| {
+                    let title = Path::new(name).file_stem().unwrap_or_default();
+                    let mut pdf = Vec::new();
+                    pdf::write_pdf(&mut pdf, &title.to_string_lossy(), pages)?;
+                    Ok(pdf)
+                },
+            )?;
+            match written {
+                Some((name, renamed)) => {
+                    if renamed {
+                        stats.renamed += 1;
+                    }
+                    pdf_name = Some(name);
+                    if let Some(name) = &pdf_name {
+                        for row in &mut manifest_rows {
+                            let key = (row.folder.clone(), row.sequence);
+                            if let Some((first, last)) = pdf_ranges.get(&key) {
+                                row.outputs.push(if first == last {
+                                    format!("{} page {}", name, first)
+                                } else {
+                                    format!("{} pages {}–{}", name, first, last)
+                                });
+                            }
+                        }
+                    }
+                }
+                None => stats.skipped += 1,
+            }
+        }
+    }
+
+    // Written even for a cancelled run, covering the images that were reached.
+    // A retry only redoes some images, so their rows replace the old ones in the existing file.
+    if options_arc.write_manifest {
+        let written = match &*target_arc {
+            OutputTarget::Folder(spl_folder) => manifest::write_manifest(
+                &spl_folder.join(manifest::MANIFEST_FILE_NAME),
+                manifest_rows,
+                only.is_some(),
+            )
+            .map_err(IrsError::from),
+            OutputTarget::Archive(_) => target_arc
+                .write(
+                    "",
+                    manifest::MANIFEST_FILE_NAME,
+                    OverwritePolicy::Overwrite,
+                    |_| Ok(manifest::format_manifest(manifest_rows, None).into_bytes()),
+                )
+                .map(|_| ()),
+        };
+        written?;
+    }
+
+    // The workers are done with the archive, so this is the last reference to it. Its directory
+    // is written even for a cancelled run, keeping the entries that were reached readable.
+    if let Ok(OutputTarget::Archive(zip)) = std::sync::Arc::try_unwrap(target_arc) {
+        use std::io::Write;
+
+        let finished = zip
+            .into_inner()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+            .finish()
+            .map_err(std::io::Error::other)
+            .and_then(|mut file| file.flush());
+        finished.map_err(|source| IrsError::OutputDir {
+            path: output_path.clone(),
+            source,
+        })?;
+    }
+
+    Ok(ProcessSummary {
+        processed,
+        stats,
+        failed,
+        failed_ids,
+        cancelled,
+        pdf: pdf_name,
+        folder: output_path,
+    })
+}
+
+// The notification for a run that stopped with `err`, saying which stage it stopped in
+fn processing_error_message(err: &IrsError) -> String {
+    match err {
+        IrsError::Refused(_) | IrsError::OutputDir { .. } => format!("✗ {}", err),
+        IrsError::Io(_) => format!("✗ Could not write the output: {}", err),
+        IrsError::Decode(_) => format!("✗ Could not read an image: {}", err),
+        IrsError::Encode(_) | IrsError::InvalidJpeg(_) => {
+            format!("✗ Could not encode the output: {}", err)
+        }
+    }
+}
+
+// Resolve the thread setting, where 0 means one worker per core the OS lets us use
+fn worker_count(requested: usize) -> usize {
+    if requested > 0 {
+        return requested.min(MAX_THREADS);
+    }
+    std::thread::available_parallelism()
+        .map(|cores| cores.get())
+        .unwrap_or(2)
+}
+
+// Spread the images over at most `workers` contiguous chunks so each thread gets a similar share
+fn chunk_size(total_images: usize, workers: usize) -> usize {
+    total_images.div_ceil(workers.max(1)).max(1)
+}
+
+// The output folder name must be a single, ordinary path component
+fn validate_subfolder_name(name: &str) -> Result<(), String> {
+    let name = name.trim();
+    if name.is_empty() {
+        return Err("Output folder name is empty".to_string());
+    }
+    if name == "." || name == ".." || name.contains(INVALID_FILENAME_CHARS) {
+        return Err(format!("\"{}\" is not a valid folder name", name));
+    }
+    Ok(())
+}
+
+// A file name template may only use known tokens and characters a file system accepts, and
+// needs `{seq}` or `{orig}` so different images can't share a name. Empty means no template.
+fn validate_name_template(template: &str) -> Result<(), String> {
+    let template = template.trim();
+    if template.is_empty() {
+        return Ok(());
+    }
+    if template.contains(INVALID_FILENAME_CHARS) {
+        return Err(format!(
+            "The file name template \"{}\" contains characters not allowed in file names",
+            template
+        ));
+    }
+    let mut identifies_image = false;
+    let mut rest = template;
+    while let Some(start) = rest.find('{') {
+        let Some(length) = rest[start..].find('}') else {
+            return Err("The file name template has a { without a closing }".to_string());
+        };
+        if rest[..start].contains('}') {
+            return Err("The file name template has a } without an opening {".to_string());
+        }
+        let token = &rest[start + 1..start + length];
+        if !NAME_TEMPLATE_TOKENS.contains(&token) {
+            return Err(format!(
+                "Unknown token {{{}}} in the file name template",
+                token
+            ));
+        }
+        identifies_image |= token == "seq" || token == "orig";
+        rest = &rest[start + length + 1..];
+    }
+    if rest.contains('}') {
+        return Err("The file name template has a } without an opening {".to_string());
+    }
+    if !identifies_image {
+        return Err(
+            "The file name template needs {seq} or {orig} to tell images apart".to_string(),
+        );
+    }
+    Ok(())
+}
+
+// Fill in a validated template for one output file:
+// `{seq}` the padded sequence number, `{side}` the part number (0 for a kept original),
+// `{orig}` the source file name without its extension (with `_p2` etc. for a TIFF page),
+// `{date}` the source's modification date as YYYYMMDD and `{width}` its width as split.
+// Tokens are replaced in one pass, so braces inside a source's own name are left alone.
+fn expand_name_template(
+    template: &str,
+    item: &ImageItem,
+    sequence_num: usize,
+    part: usize,
+    padding: usize,
+) -> String {
+    let mut name = String::new();
+    let mut rest = template;
+    while let Some(start) = rest.find('{') {
+        name.push_str(&rest[..start]);
+        rest = &rest[start..];
+        let Some(length) = rest.find('}') else {
+            break;
+        };
+        match &rest[1..length] {
+            "seq" => name.push_str(&pad_number(sequence_num, padding)),
+            "side" => name.push_str(&part.to_string()),
+            "orig" => {
+                name.push_str(&item.path.file_stem().unwrap_or_default().to_string_lossy());
+                if let Some(page) = item.page {
+                    name.push_str(&format!("_p{}", page + 1));
+                }
+            }
+            "date" => {
+                // A file system that doesn't record the time falls back to today
+                let date = match item.modified {
+                    Some(modified) => chrono::DateTime::<chrono::Local>::from(modified),
+                    None => chrono::Local::now(),
+                };
+                name.push_str(&date.format("%Y%m%d").to_string());
+            }
+            "width" => name.push_str(&rotated_dimensions(item).0.to_string()),
+            _ => name.push_str(&rest[..=length]),
+        }
+        rest = &rest[length + 1..];
+    }
+    name.push_str(rest);
+    name
+}
+
+// The run's folder name as a ZIP file, e.g. `SPL.zip`
+fn output_archive_name(options: &OutputOptions) -> String {
+    format!(
+        "{}.{}",
+        output_subfolder_name(options),
+        archive::ARCHIVE_EXTENSION
+    )
+}
+
+fn output_subfolder_name(options: &OutputOptions) -> String {
+    let name = options.subfolder.trim();
+    if options.timestamp_subfolder {
+        format!("{}_{}", name, chrono::Local::now().format("%Y%m%d_%H%M"))
+    } else {
+        name.to_string()
+    }
+}
+
+// Window title while a run is in progress, e.g. "IRS — Processing 45% (90/200)"
+fn progress_title(done: usize, total: usize) -> String {
+    let percent = (done * 100).checked_div(total).unwrap_or(0);
+    format!("IRS — Processing {}% ({}/{})", percent, done, total)
+}
+
+// Loaded images whose source file has been moved or deleted since, limited to `only` if given
+struct MissingSources {
+    ids: Vec<usize>,
+    // Each missing file once, even when several of its TIFF pages are loaded
+    files: Vec<String>,
+}
+
+impl MissingSources {
+    fn message(&self) -> String {
+        let names = summarize_names(&self.files);
+        if self.files.len() == 1 {
+            format!("1 source file no longer exists: {}", names)
+        } else {
+            format!(
+                "{} source files no longer exist: {}",
+                self.files.len(),
+                names
+            )
+        }
+    }
+}
+
+fn find_missing_sources(
+    images: &[ImageItem],
+    only: Option<&HashSet<usize>>,
+) -> Option<MissingSources> {
+    let mut missing = MissingSources {
+        ids: Vec::new(),
+        files: Vec::new(),
+    };
+    for item in images {
+        if only.is_some_and(|ids| !ids.contains(&item.id)) || archive::exists(&item.path) {
+            continue;
+        }
+        missing.ids.push(item.id);
+        let file = item.path.display().to_string();
+        if !missing.files.contains(&file) {
+            missing.files.push(file);
+        }
+    }
+    (!missing.ids.is_empty()).then_some(missing)
+}
+
+// List file names for a notification, eliding the tail of long lists
+fn summarize_names(names: &[String]) -> String {
+    const SHOWN: usize = 5;
+    let mut summary = names
+        .iter()
+        .take(SHOWN)
+        .cloned()
+        .collect::<Vec<_>>()
+        .join(", ");
+    if names.len() > SHOWN {
+        summary.push_str(&format!(" and {} more", names.len() - SHOWN));
+    }
+    summary
+}
+
+// JPEG quality only makes sense in 1..=100; out-of-range input is pulled to the nearest bound
+fn clamp_quality(value: i64) -> u8 {
+    value.clamp(1, 100) as u8
+}
+
+fn pad_number(num: usize, width: usize) -> String {
+    format!("{:0width$}", num, width = width)
+}
+
+// The bundled PDF is named after the prefix, e.g. `CH03_pages.pdf`
+fn pdf_file_name(options: &OutputOptions) -> String {
+    format!("{}pages.pdf", options.prefix)
+}
+
+// Build e.g. `CH03_001_1.jpg` from the prefix, the padded sequence number and the part number.
+// In rename-only mode there is a single part and the name has no part suffix, e.g. `CH03_001.jpg`.
+// A name template, if set, takes the place of everything between the prefix and the extension.
+fn output_file_name(
+    options: &OutputOptions,
+    item: &ImageItem,
+    sequence_num: usize,
+    part: usize,
+) -> String {
+    let template = options.name_template.trim();
+    if !template.is_empty() {
+        return format!(
+            "{}{}.{}",
+            options.prefix,
+            expand_name_template(template, item, sequence_num, part, options.padding),
+            options.format.extension()
+        );
+    }
+    if options.rename_only {
+        return format!(
+            "{}{}.{}",
+            options.prefix,
+            pad_number(sequence_num, options.padding),
+            options.format.extension()
+        );
+    }
+    format!(
+        "{}{}_{}.{}",
+        options.prefix,
+        pad_number(sequence_num, options.padding),
+        part,
+        options.format.extension()
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // A fresh `irs-{name}` folder under the temp dir holding `count` blank 4×2 PNGs, loaded as
+    // `scan0.png`, `scan1.png`, … with ids 0, 1, …
+    fn png_batch(name: &str, count: usize) -> (PathBuf, Vec<ImageItem>) {
+        let dir = std::env::temp_dir().join(format!("irs-{name}"));
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(&dir).unwrap();
+        let images = (0..count)
+            .map(|id| png_item(id, dir.join(format!("scan{id}.png"))))
+            .collect();
+        (dir, images)
+    }
+
+    // A blank 4×2 PNG saved at `path`, creating its folder, and its item
+    fn png_item(id: usize, path: PathBuf) -> ImageItem {
+        std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+        image::RgbImage::new(4, 2).save(&path).unwrap();
+        ImageItem {
+            width: 4,
+            height: 2,
+            ..placeholder_item(id, path)
+        }
+    }
+
+    #[test]
+    fn base64_matches_rfc4648_vectors() {
+        let vectors: &[(&[u8], &str)] = &[
+            (b"", ""),
+            (b"f", "Zg=="),
+            (b"fo", "Zm8="),
+            (b"foo", "Zm9v"),
+            (b"foob", "Zm9vYg=="),
+            (b"fooba", "Zm9vYmE="),
+            (b"foobar", "Zm9vYmFy"),
+        ];
+        for (input, expected) in vectors {
+            assert_eq!(encode_to_base64(input).unwrap(), *expected);
+            assert_eq!(decode_base64(expected).as_deref(), Some(*input));
+        }
+        assert_eq!(decode_base64("Zm9"), None);
+        assert_eq!(decode_base64("Zm9*"), None);
+    }
+
+    #[test]
+    fn preview_cuts_follow_the_output() {
+        let item = ImageItem {
+            width: 900,
+            height: 600,
+            split_ratio: 0.4,
+            ..placeholder_item(0, PathBuf::from("spread.jpg"))
+        };
+        let options = OutputOptions::default();
+        assert_eq!(preview_cuts(&item, &options, Some(0.55)), vec![0.4]);
+
+        // A found gutter only stands in for the default centre cut
+        let centred = ImageItem {
+            split_ratio: DEFAULT_SPLIT_RATIO,
+            ..item.clone()
+        };
+        let detecting = OutputOptions {
+            detect_gutter: true,
+            ..OutputOptions::default()
+        };
+        assert_eq!(preview_cuts(&centred, &detecting, Some(0.55)), vec![0.55]);
+        assert_eq!(preview_cuts(&centred, &detecting, None), vec![0.5]);
+
+        let strips = OutputOptions {
+            splits: 3,
+            ..OutputOptions::default()
+        };
+        assert_eq!(
+            preview_cuts(&item, &strips, None),
+            vec![300.0 / 900.0, 600.0 / 900.0]
+        );
+
+        let portrait = ImageItem {
+            rotation: 90,
+            ..item
+        };
+        let landscape_only = OutputOptions {
+            landscape_only: true,
+            ..OutputOptions::default()
+        };
+        assert!(preview_cuts(&portrait, &landscape_only, None).is_empty());
+    }
+
+    #[test]
+    fn base64_encodes_high_bytes() {
+        assert_eq!(
+            encode_to_base64(&[0xFF, 0xD8, 0xFF, 0xE0]).unwrap(),
+            "/9j/4A=="
+        );
+        assert_eq!(encode_to_base64(&[0xFB, 0xFF]).unwrap(), "+/8=");
+    }
+
+    #[test]
+    fn cli_args_override_the_defaults() {
+        let args = |line: &str| line.split(' ').map(String::from).collect::<Vec<_>>();
+        let parsed = cli::parse_args(&args(
+            "--input ./scans --output ./out --split horizontal --dpi 600 --quality 90",
+        ))
+        .unwrap()
+        .unwrap();
+        assert_eq!(parsed.input, PathBuf::from("./scans"));
+        assert_eq!(parsed.output, PathBuf::from("./out"));
+        assert_eq!(parsed.options.split_mode, SplitMode::Horizontal);
+        assert_eq!(parsed.options.dpi, 600);
+        assert_eq!(parsed.options.quality, 90);
+        assert_eq!(parsed.options.splits, DEFAULT_SPLITS);
+        assert_eq!(parsed.options.flip_right, Flip::None);
+        let flipped = cli::parse_args(&args("--input a --output b --flip-right horizontal"));
+        assert_eq!(
+            flipped.unwrap().unwrap().options.flip_right,
+            Flip::Horizontal
+        );
+        assert!(cli::parse_args(&args("--input a --output b --flip-left sideways")).is_err());
+
+        assert_eq!(cli::parse_args(&args("--help")), Ok(None));
+        assert!(cli::parse_args(&args("--input ./scans")).is_err());
+        assert!(cli::parse_args(&args("--input ./scans --output ./out --quality 0")).is_err());
+        assert!(cli::parse_args(&args("--input ./scans --output")).is_err());
+    }
+
+    #[test]
+    fn progress_title_shows_percent_and_count() {
+        assert_eq!(progress_title(90, 200), "IRS — Processing 45% (90/200)");
+        assert_eq!(progress_title(0, 0), "IRS — Processing 0% (0/0)");
+    }
+
+    #[test]
+    fn file_size_uses_readable_units() {
+        assert_eq!(format_file_size(512), "512 B");
+        assert_eq!(format_file_size(1536), "1.5 KB");
+        assert_eq!(format_file_size(2_202_010), "2.1 MB");
+    }
+
+    #[test]
+    fn natural_order_compares_numbers_by_value() {
+        let mut names = vec![
+            "img10.jpg",
+            "Img3.jpg",
+            "img1.jpg",
+            "img02.jpg",
+            "cover.jpg",
+        ];
+        names.sort_by(|a, b| scan::natural_cmp(a, b));
+        assert_eq!(
+            names,
+            vec![
+                "cover.jpg",
+                "img1.jpg",
+                "img02.jpg",
+                "Img3.jpg",
+                "img10.jpg"
+            ]
+        );
+    }
+
+    #[test]
+    fn split_image_gives_remainder_to_last_strip() {
+        let img = image::DynamicImage::new_rgb8(10, 4);
+        let widths: Vec<u32> =
+            split_image(&img, SplitMode::Vertical, 3, DEFAULT_SPLIT_RATIO, 0, false)
+                .iter()
+                .map(|piece| piece.width())
+                .collect();
+        assert_eq!(widths, vec![3, 3, 4]);
+
+        let heights: Vec<u32> = split_image(
+            &img,
+            SplitMode::Horizontal,
+            3,
+            DEFAULT_SPLIT_RATIO,
+            0,
+            false,
+        )
+        .iter()
+        .map(|piece| piece.height())
+        .collect();
+        assert_eq!(heights, vec![1, 1, 2]);
+    }
+
+    #[test]
+    fn balanced_halves_drop_the_centre_column() {
+        // Each column's red channel holds its x, so the pieces show which columns they kept
+        let img = image::DynamicImage::ImageRgb8(image::RgbImage::from_fn(9, 2, |x, _| {
+            image::Rgb([x as u8, 0, 0])
+        }));
+        let columns = |piece: &image::DynamicImage| -> Vec<u8> {
+            let rgb = piece.to_rgb8();
+            (0..rgb.width()).map(|x| rgb[(x, 0)][0]).collect()
+        };
+
+        let pieces = split_image(&img, SplitMode::Vertical, 2, DEFAULT_SPLIT_RATIO, 0, true);
+        assert_eq!(columns(&pieces[0]), vec![0, 1, 2, 3]);
+        assert_eq!(columns(&pieces[1]), vec![5, 6, 7, 8]);
+
+        let pieces = split_image(&img, SplitMode::Vertical, 2, DEFAULT_SPLIT_RATIO, 1, true);
+        assert_eq!(columns(&pieces[0]), vec![0, 1, 2, 3, 4]);
+        assert_eq!(columns(&pieces[1]), vec![4, 5, 6, 7, 8]);
+
+        // Off, or with the divider moved, the odd column stays with the second half
+        let pieces = split_image(&img, SplitMode::Vertical, 2, DEFAULT_SPLIT_RATIO, 0, false);
+        assert_eq!(columns(&pieces[1]), vec![4, 5, 6, 7, 8]);
+        let pieces = split_image(&img, SplitMode::Vertical, 2, 0.3, 0, true);
+        assert_eq!((pieces[0].width(), pieces[1].width()), (2, 7));
+    }
+
+    #[test]
+    fn detect_gutter_finds_the_dark_band() {
+        let mut img = image::RgbImage::from_pixel(200, 20, image::Rgb([240, 240, 240]));
+        for x in 120..124 {
+            for y in 0..20 {
+                img.put_pixel(x, y, image::Rgb([60, 60, 60]));
+            }
+        }
+        let img = image::DynamicImage::ImageRgb8(img);
+        let ratio = detect_gutter(&img, SplitMode::Vertical, 30).unwrap();
+        assert!((0.6..=0.62).contains(&ratio), "{ratio}");
+
+        // Outside the searched band it isn't found, and a blank page has no gutter at all
+        assert_eq!(detect_gutter(&img, SplitMode::Vertical, 10), None);
+        let blank = image::DynamicImage::ImageRgb8(image::RgbImage::from_pixel(
+            200,
+            20,
+            image::Rgb([240, 240, 240]),
+        ));
+        assert_eq!(detect_gutter(&blank, SplitMode::Vertical, 30), None);
+    }
+
+    #[test]
+    fn page_filter_keeps_full_run_numbering() {
+        let item = |id, width| ImageItem {
+            width,
+            height: 300,
+            ..placeholder_item(id, PathBuf::from(format!("scan{id}.jpg")))
+        };
+        // The portrait cover stays whole, so it is page 1 and the spread after it pages 2 and 3
+        let images = [item(0, 200), item(1, 400), item(2, 400)];
+        let options = OutputOptions {
+            landscape_only: true,
+            page_filter: PageFilter::Even,
+            ..OutputOptions::default()
+        };
+        let spl_folder = std::env::temp_dir().join("irs-page-filter-missing-folder");
+        assert_eq!(first_pages(&images, &options), vec![1, 2, 4]);
+        assert_eq!(
+            plan_output_names(&images, &spl_folder, &options),
+            vec![
+                "scan0.jpg → nothing".to_string(),
+                "scan1.jpg → 02_1.jpg".to_string(),
+                "scan2.jpg → 03_1.jpg".to_string(),
+            ]
+        );
+
+        let options = OutputOptions {
+            page_filter: PageFilter::Odd,
+            ..options
+        };
+        assert_eq!(
+            plan_output_names(&images, &spl_folder, &options),
+            vec![
+                "scan0.jpg → 01_1.jpg".to_string(),
+                "scan1.jpg → 02_2.jpg".to_string(),
+                "scan2.jpg → 03_2.jpg".to_string(),
+            ]
+        );
+    }
+
+    #[test]
+    fn split_mode_override_wins_over_the_setting() {
+        let item = |id, split_mode| ImageItem {
+            width: 400,
+            height: 300,
+            split_mode,
+            ..placeholder_item(id, PathBuf::from(format!("scan{id}.jpg")))
+        };
+        let images = [item(0, None), item(1, Some(SplitMode::Quadrants))];
+        let options = OutputOptions::default();
+        assert_eq!(planned_parts(&images[0], &options), 2);
+        assert_eq!(planned_parts(&images[1], &options), 4);
+
+        let spl_folder = std::env::temp_dir().join("irs-split-override-missing-folder");
+        assert_eq!(
+            plan_output_names(&images, &spl_folder, &options)[1],
+            "scan1.jpg → 02_1.jpg, 02_2.jpg, 02_3.jpg, 02_4.jpg"
+        );
+    }
+
+    #[test]
+    fn quadrants_keep_every_pixel_in_reading_order() {
+        let mut img = image::RgbImage::new(5, 3);
+        img.put_pixel(4, 2, image::Rgb([255, 0, 0]));
+        let img = image::DynamicImage::ImageRgb8(img);
+        let pieces = split_image(&img, SplitMode::Quadrants, 2, 0.3, 0, false);
+        let sizes: Vec<(u32, u32)> = pieces.iter().map(|piece| piece.dimensions()).collect();
+        assert_eq!(sizes, vec![(2, 1), (3, 1), (2, 2), (3, 2)]);
+        // The bottom-right corner pixel ends up in the last piece
+        assert_eq!(pieces[3].get_pixel(2, 1), image::Rgba([255, 0, 0, 255]));
+
+        let item = ImageItem {
+            width: 5,
+            height: 3,
+            ..placeholder_item(0, PathBuf::from("sheet.jpg"))
+        };
+        let options = OutputOptions {
+            split_mode: SplitMode::Quadrants,
+            splits: 3,
+            ..OutputOptions::default()
+        };
+        assert_eq!(planned_parts(&item, &options), 4);
+    }
+
+    #[test]
+    fn halves_are_flipped_independently_before_numbering() {
+        let dir = std::env::temp_dir().join("irs-flip");
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(&dir).unwrap();
+        // A marker in the top-left corner of each half
+        let mut img = image::RgbImage::new(4, 2);
+        img.put_pixel(0, 0, image::Rgb([255, 0, 0]));
+        img.put_pixel(2, 0, image::Rgb([0, 0, 255]));
+        let item = ImageItem {
+            width: 4,
+            height: 2,
+            ..placeholder_item(0, dir.join("film.png"))
+        };
+        img.save(&item.path).unwrap();
+        let cut = |options: OutputOptions| cut_image(&item, &options).unwrap().1;
+        let red = image::Rgba([255, 0, 0, 255]);
+        let blue = image::Rgba([0, 0, 255, 255]);
+
+        let pieces = cut(OutputOptions {
+            flip_right: Flip::Horizontal,
+            ..OutputOptions::default()
+        });
+        assert_eq!(pieces[0].get_pixel(0, 0), red);
+        assert_eq!(pieces[1].get_pixel(1, 0), blue);
+
+        // The right half stays the flipped one when it's numbered first
+        let pieces = cut(OutputOptions {
+            flip_right: Flip::Both,
+            reading_order: ReadingOrder::RightToLeft,
+            ..OutputOptions::default()
+        });
+        assert_eq!(pieces[0].get_pixel(1, 1), blue);
+        assert_eq!(pieces[1].get_pixel(0, 0), red);
+
+        let pieces = cut(OutputOptions {
+            flip_left: Flip::Vertical,
+            splits: 4,
+            ..OutputOptions::default()
+        });
+        let _ = std::fs::remove_dir_all(&dir);
+        // Four strips have no halves, so nothing is flipped
+        assert_eq!(pieces.len(), 4);
+        assert_eq!(pieces[0].get_pixel(0, 0), red);
+    }
+
+    #[test]
+    fn split_preview_shows_the_pieces_without_writing() {
+        let dir = std::env::temp_dir().join("irs-split-preview");
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(&dir).unwrap();
+        let item = ImageItem {
+            width: 6,
+            height: 2,
+            ..placeholder_item(0, dir.join("spread.png"))
+        };
+        image::RgbImage::new(6, 2).save(&item.path).unwrap();
+        let options = OutputOptions {
+            page_filter: PageFilter::Odd,
+            ..OutputOptions::default()
+        };
+
+        let pieces = preview_split(&item, 3, 1, &options).unwrap();
+        let written: Vec<_> = std::fs::read_dir(&dir).unwrap().collect();
+        let _ = std::fs::remove_dir_all(&dir);
+
+        let summary: Vec<_> = pieces
+            .iter()
+            .map(|piece| (piece.name.as_str(), piece.width, piece.written))
+            .collect();
+        assert_eq!(summary, vec![("03_1.jpg", 3, true), ("03_2.jpg", 3, false)]);
+        assert_eq!(written.len(), 1);
+    }
+
+    #[test]
+    fn retry_only_writes_the_chosen_images_under_their_numbers() {
+        let (dir, images) = png_batch("retry-only", 3);
+
+        let summary = process_images_sync(
+            images,
+            Some(HashSet::from([1])),
+            dir.clone(),
+            OutputOptions::default(),
+            std::sync::Arc::new(AtomicBool::new(false)),
+            |_, _, _| {},
+        )
+        .unwrap();
+        let mut written: Vec<String> = std::fs::read_dir(&summary.folder)
+            .unwrap()
+            .map(|entry| entry.unwrap().file_name().to_string_lossy().to_string())
+            .collect();
+        written.sort();
+        let _ = std::fs::remove_dir_all(&dir);
+
+        assert_eq!(summary.processed, 1);
+        assert_eq!(written, vec!["02_1.jpg", "02_2.jpg"]);
+    }
+
+    #[test]
+    fn cover_previews_show_the_middle_of_the_longer_side() {
+        assert_eq!(cover_window(400, 200, SplitMode::Vertical), (0.25, 0.5));
+        assert_eq!(cover_window(400, 200, SplitMode::Horizontal), (0.0, 1.0));
+        assert_eq!(cover_window(300, 600, SplitMode::Horizontal), (0.25, 0.5));
+        assert_eq!(cover_window(0, 0, SplitMode::Quadrants), (0.0, 1.0));
+    }
+
+    #[test]
+    fn filter_matches_file_names_ignoring_case() {
+        let item = placeholder_item(0, PathBuf::from("/scans/Chapter-03/Page_012.TIF"));
+        assert!(matches_filter(&item, ""));
+        assert!(matches_filter(&item, "  "));
+        assert!(matches_filter(&item, "page_01"));
+        assert!(matches_filter(&item, " .tif"));
+        // Only the file name counts, not the folders it's in
+        assert!(!matches_filter(&item, "chapter"));
+    }
+
+    #[test]
+    fn missing_sources_stop_the_run_before_anything_is_written() {
+        let (dir, images) = png_batch("missing-sources", 3);
+        std::fs::remove_file(&images[0].path).unwrap();
+        std::fs::remove_file(&images[2].path).unwrap();
+
+        let missing = find_missing_sources(&images, None).unwrap();
+        assert_eq!(missing.ids, vec![0, 2]);
+        assert!(find_missing_sources(&images, Some(&HashSet::from([1]))).is_none());
+
+        let options = OutputOptions::default();
+        let result = process_images_sync(
+            images,
+            None,
+            dir.clone(),
+            options.clone(),
+            std::sync::Arc::new(AtomicBool::new(false)),
+            |_, _, _| {},
+        );
+        let created = dir.join(output_subfolder_name(&options)).exists();
+        let _ = std::fs::remove_dir_all(&dir);
+
+        assert!(matches!(
+            result,
+            Err(IrsError::Refused(message)) if message.starts_with("2 source files no longer exist")
+        ));
+        assert!(!created);
+        let one = MissingSources {
+            ids: vec![0],
+            files: vec!["scan.jpg".to_string()],
+        };
+        assert!(one
+            .message()
+            .starts_with("1 source file no longer exists: "));
     }
-}
 
-#[component]
-fn ImageCard(
-    item: ImageItem,
-    drag_source: Signal<Option<usize>>,
-    drag_over_id: Signal<Option<usize>>,
-    images: Signal<Vec<ImageItem>>,
-) -> Element {
-    let item_id = item.id;
-    let is_drag_over = drag_over_id() == Some(item_id);
-    let thumbnail = item.thumbnail_base64.clone();
-    let item_name = item
-        .path
-        .file_name()
-        .unwrap_or_default()
-        .to_string_lossy()
-        .to_string();
+    #[test]
+    fn errors_say_which_stage_failed() {
+        let dir = std::env::temp_dir().join("irs-errors");
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(&dir).unwrap();
+        let garbage = dir.join("scan.png");
+        std::fs::write(&garbage, b"not an image").unwrap();
+        let blocker = dir.join("taken");
+        std::fs::write(&blocker, b"").unwrap();
 
-    // Handler to move the item left (earlier in the list)
-    let move_left = {
-        let mut images = images.clone();
-        move |_| {
-            let mut imgs = images.read().clone();
-            if let Some(idx) = imgs.iter().position(|img| img.id == item_id) {
-                if idx > 0 {
-                    imgs.swap(idx, idx - 1);
-                    images.set(imgs);
-                }
-            }
-        }
-    };
+        let decoded = open_image(&garbage, None, false);
+        let mut not_jpeg = vec![0u8; 8];
+        let run = |save_folder: PathBuf, options: OutputOptions| {
+            process_images_sync(
+                vec![placeholder_item(0, garbage.clone())],
+                None,
+                save_folder,
+                options,
+                std::sync::Arc::new(AtomicBool::new(false)),
+                |_, _, _| {},
+            )
+        };
+        let refused = run(
+            dir.clone(),
+            OutputOptions {
+                subfolder: "..".to_string(),
+                ..OutputOptions::default()
+            },
+        );
+        // A file where the output folder should go
+        let blocked = run(blocker.clone(), OutputOptions::default());
+        let _ = std::fs::remove_dir_all(&dir);
 
-    // Handler to move the item right (later in the list)
-    let move_right = {
-        let mut images = images.clone();
-        move |_| {
-            let mut imgs = images.read().clone();
-            if let Some(idx) = imgs.iter().position(|img| img.id == item_id) {
-                if idx + 1 < imgs.len() {
-                    imgs.swap(idx, idx + 1);
-                    images.set(imgs);
-                }
-            }
+        assert!(matches!(decoded, Err(IrsError::Decode(_))));
+        assert!(matches!(
+            set_jpeg_dpi(&mut not_jpeg, 300),
+            Err(IrsError::InvalidJpeg(_))
+        ));
+        assert!(matches!(refused, Err(IrsError::Refused(_))));
+        let err = blocked.unwrap_err();
+        assert!(matches!(&err, IrsError::OutputDir { path, .. } if path.starts_with(&blocker)));
+        assert!(processing_error_message(&err).starts_with("✗ Failed to create "));
+        assert_eq!(
+            processing_error_message(&IrsError::decode("Unrecognized image format")),
+            "✗ Could not read an image: Unrecognized image format"
+        );
+    }
+
+    #[test]
+    fn images_in_a_zip_archive_are_split_without_extracting_them() {
+        use std::io::Write;
+
+        let dir = std::env::temp_dir().join("irs-archive");
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(&dir).unwrap();
+        let zip_path = dir.join("batch.zip");
+        let mut png = std::io::Cursor::new(Vec::new());
+        image::RgbImage::new(4, 2)
+            .write_to(&mut png, ImageFormat::Png)
+            .unwrap();
+        let mut zip = zip::ZipWriter::new(std::fs::File::create(&zip_path).unwrap());
+        for name in [
+            "ch1/page2.png",
+            "ch1/page10.png",
+            "__MACOSX/ch1/._page2.png",
+        ] {
+            zip.start_file(name, zip::write::SimpleFileOptions::default())
+                .unwrap();
+            zip.write_all(png.get_ref()).unwrap();
         }
-    };
+        zip.start_file("notes.txt", zip::write::SimpleFileOptions::default())
+            .unwrap();
+        zip.finish().unwrap();
 
-    rsx! {
-        div {
-            class: "image-item",
-            class: if is_drag_over { "drag-over" } else { "" },
-            draggable: true,
-            tabindex: "0",
-            onkeydown: move |evt: KeyboardEvent| {
-                // Use Arrow keys to reorder focused image card
-                match evt.key() {
-                    Key::ArrowLeft => {
-                        let mut imgs = images.read().clone();
-                        if let Some(idx) = imgs.iter().position(|img| img.id == item_id) {
-                            if idx > 0 {
-                                imgs.swap(idx, idx - 1);
-                                images.set(imgs);
-                            }
-                        }
-                    }
-                    Key::ArrowRight => {
-                        let mut imgs = images.read().clone();
-                        if let Some(idx) = imgs.iter().position(|img| img.id == item_id) {
-                            if idx + 1 < imgs.len() {
-                                imgs.swap(idx, idx + 1);
-                                images.set(imgs);
-                            }
-                        }
-                    }
-                    _ => {}
+        let paths = archive::image_paths(&zip_path).unwrap();
+        assert_eq!(
+            paths,
+            vec![
+                zip_path.join("ch1/page2.png"),
+                zip_path.join("ch1/page10.png")
+            ]
+        );
+        assert_eq!(
+            archive::split(&paths[1]),
+            Some((zip_path.as_path(), "ch1/page10.png".to_string()))
+        );
+        assert_eq!(archive::split(&zip_path), None);
+
+        let images: Vec<ImageItem> = paths
+            .into_iter()
+            .enumerate()
+            .map(|(id, path)| {
+                let info = read_image_info(&path, None, true).unwrap();
+                ImageItem {
+                    width: info.width,
+                    height: info.height,
+                    file_size: info.file_size,
+                    ..placeholder_item(id, path)
                 }
-            },
-            ondragstart: move |_| {
-                drag_source.set(Some(item_id));
-            },
-            ondragover: move |evt: DragEvent| {
-                evt.prevent_default();
-                drag_over_id.set(Some(item_id));
-            },
-            ondrop: move |evt: DragEvent| {
-                evt.prevent_default();
+            })
+            .collect();
+        assert_eq!((images[0].width, images[0].height), (4, 2));
+        assert!(find_missing_sources(&images, None).is_none());
 
-                if let Some(source_id) = drag_source() {
-                    if source_id != item_id {
-                        let mut imgs = images.read().clone();
+        let summary = process_images_sync(
+            images,
+            None,
+            dir.clone(),
+            OutputOptions::default(),
+            std::sync::Arc::new(AtomicBool::new(false)),
+            |_, _, _| {},
+        )
+        .unwrap();
+        let mut written: Vec<String> = std::fs::read_dir(&summary.folder)
+            .unwrap()
+            .map(|entry| entry.unwrap().file_name().to_string_lossy().to_string())
+            .collect();
+        written.sort();
+        let _ = std::fs::remove_dir_all(&dir);
 
-                        let source_idx = imgs.iter().position(|img| img.id == source_id);
-                        let target_idx = imgs.iter().position(|img| img.id == item_id);
+        assert_eq!(summary.processed, 2);
+        assert_eq!(
+            written,
+            vec!["01_1.jpg", "01_2.jpg", "02_1.jpg", "02_2.jpg"]
+        );
+    }
 
-                        if let (Some(src), Some(tgt)) = (source_idx, target_idx) {
-                            imgs.swap(src, tgt);
-                            images.set(imgs);
-                        }
-                    }
-                }
-                drag_source.set(None);
-                drag_over_id.set(None);
-            },
-            ondragleave: move |_| {
-                drag_over_id.set(None);
+    #[test]
+    fn zip_output_bundles_the_pieces_into_one_archive() {
+        use std::io::Read;
+
+        let (dir, images) = png_batch("zip-output", 2);
+        let options = OutputOptions {
+            zip_output: true,
+            write_manifest: true,
+            ..OutputOptions::default()
+        };
+        let summary = process_images_sync(
+            images,
+            None,
+            dir.join("out"),
+            options.clone(),
+            std::sync::Arc::new(AtomicBool::new(false)),
+            |_, _, _| {},
+        )
+        .unwrap();
+        let zip_path = dir.join("out").join(output_archive_name(&options));
+        let pieces = archive::image_paths(&zip_path).unwrap();
+        let mut manifest = String::new();
+        archive::open(&zip_path.join(manifest::MANIFEST_FILE_NAME))
+            .unwrap()
+            .read_to_string(&mut manifest)
+            .unwrap();
+        let piece = image::load(archive::open(&pieces[0]).unwrap(), ImageFormat::Jpeg).unwrap();
+        let _ = std::fs::remove_dir_all(&dir);
+
+        assert_eq!(summary.processed, 2);
+        assert_eq!(summary.folder, zip_path);
+        assert_eq!(
+            pieces,
+            ["01_1.jpg", "01_2.jpg", "02_1.jpg", "02_2.jpg"]
+                .iter()
+                .map(|name| zip_path.join(name))
+                .collect::<Vec<_>>()
+        );
+        assert_eq!(manifest.lines().count(), 3);
+        assert_eq!((piece.width(), piece.height()), (2, 2));
+    }
+
+    #[test]
+    fn capture_dates_sort_oldest_first_with_undated_images_last() {
+        assert_eq!(
+            parse_exif_date("2023:06:01 09:30:00\0"),
+            Some("2023:06:01 09:30:00".to_string())
+        );
+        assert_eq!(parse_exif_date("0000:00:00 00:00:00"), None);
+        assert_eq!(parse_exif_date("    :  :     :  :  "), None);
+        assert_eq!(parse_exif_date("2023-06-01"), None);
+
+        let item = |id: usize, captured: Option<&str>| ImageItem {
+            captured: captured.map(str::to_string),
+            ..placeholder_item(id, PathBuf::from(format!("scan{id}.jpg")))
+        };
+        let images = vec![
+            item(0, None),
+            item(1, Some("2024:01:15 14:32:00")),
+            item(2, None),
+            item(3, Some("2023:06:01 09:30:00")),
+            // No EXIF date, so its modification time counts
+            ImageItem {
+                modified: Some(std::time::SystemTime::UNIX_EPOCH),
+                ..item(4, None)
             },
+        ];
+        let order: Vec<usize> = sorted_images(&images, SortOrder::Captured)
+            .iter()
+            .map(|img| img.id)
+            .collect();
+        assert_eq!(order, vec![4, 3, 1, 0, 2]);
+    }
 
-            // Control row with SVG arrows
-            div {
-                class: "move-buttons",
-                // Left arrow button (SVG)
-                button {
-                    onclick: move_left,
-                    title: "Move left",
-                    aria_label: "Move left",
-                    svg {
-                        xmlns: "http://www.w3.org/2000/svg",
-                        view_box: "0 0 24 24",
-                        width: "14",
-                        height: "14",
-                        fill: "white",
-                        path {
-                            d: "M15.41 7.41L14 6l-6 6 6 6 1.41-1.41L10.83 12z"
-                        }
-                    }
-                }
-                // Right arrow button (SVG)
-                button {
-                    onclick: move_right,
-                    title: "Move right",
-                    aria_label: "Move right",
-                    fill: "white",
-                    svg {
-                        xmlns: "http://www.w3.org/2000/svg",
-                        view_box: "0 0 24 24",
-                        width: "14",
-                        height: "14",
-                        path {
-                            d: "M8.59 16.59L10 18l6-6-6-6-1.41 1.41L13.17 12z"
-                        }
-                    }
-                }
-            }
+    #[test]
+    fn mirrored_source_folders_get_their_own_subfolders() {
+        let (dir, _) = png_batch("mirror", 0);
+        // Two different folders named `vol1`, so the second becomes `vol1_2`
+        let sources = ["vol1", "vol2", "old/vol1", "vol1"];
+        let images: Vec<ImageItem> = sources
+            .iter()
+            .enumerate()
+            .map(|(id, folder)| png_item(id, dir.join(folder).join(format!("scan{id}.png"))))
+            .collect();
+        let mut options = OutputOptions {
+            folder_structure: FolderStructure::MirrorSource,
+            ..OutputOptions::default()
+        };
+        let slots =
+            |options: &OutputOptions| -> Vec<(String, usize)> { output_slots(&images, options) };
+        let slot = |folder: &str, sequence_num| (folder.to_string(), sequence_num);
+        assert_eq!(
+            slots(&options),
+            vec![
+                slot("vol1", 1),
+                slot("vol2", 2),
+                slot("vol1_2", 3),
+                slot("vol1", 4)
+            ]
+        );
+        options.number_per_folder = true;
+        assert_eq!(
+            slots(&options),
+            vec![
+                slot("vol1", 1),
+                slot("vol2", 1),
+                slot("vol1_2", 1),
+                slot("vol1", 2)
+            ]
+        );
+        assert_eq!(find_duplicate_output(&images, &options), None);
+        assert_eq!(
+            slots(&OutputOptions::default()),
+            (1..=4).map(|n| slot("", n)).collect::<Vec<_>>()
+        );
 
-            img {
-                src: "data:image/jpeg;base64,{thumbnail}",
-                alt: "Preview",
-            }
-            div {
-                class: "image-label",
-                "{item_name}"
-            }
-        }
+        let summary = process_images_sync(
+            images.clone(),
+            None,
+            dir.clone(),
+            options,
+            std::sync::Arc::new(AtomicBool::new(false)),
+            |_, _, _| {},
+        )
+        .unwrap();
+        let written = |folder: &str| -> Vec<String> {
+            let mut names: Vec<String> = std::fs::read_dir(summary.folder.join(folder))
+                .unwrap()
+                .map(|entry| entry.unwrap().file_name().to_string_lossy().to_string())
+                .collect();
+            names.sort();
+            names
+        };
+        let vol1 = written("vol1");
+        let vol1_2 = written("vol1_2");
+        let _ = std::fs::remove_dir_all(&dir);
+
+        assert_eq!(summary.processed, 4);
+        assert_eq!(vol1, vec!["01_1.jpg", "01_2.jpg", "02_1.jpg", "02_2.jpg"]);
+        assert_eq!(vol1_2, vec!["01_1.jpg", "01_2.jpg"]);
     }
-}
 
-fn create_thumbnail(path: &PathBuf) -> Result<String, Box<dyn std::error::Error>> {
-    let img = image::open(path)?;
-    let thumbnail = img.thumbnail(THUMBNAIL_SIZE, THUMBNAIL_SIZE);
-    let rgb_img = thumbnail.to_rgb8();
+    #[test]
+    fn manifest_lists_outputs_and_retries_update_their_rows() {
+        let (dir, images) = png_batch("manifest", 2);
+        // The second file isn't an image yet, so the first run fails on it
+        std::fs::write(&images[1].path, b"not an image").unwrap();
+        let options = OutputOptions {
+            write_manifest: true,
+            ..OutputOptions::default()
+        };
+        let run = |only| {
+            process_images_sync(
+                images.clone(),
+                only,
+                dir.clone(),
+                options.clone(),
+                std::sync::Arc::new(AtomicBool::new(false)),
+                |_, _, _| {},
+            )
+            .unwrap()
+        };
+
+        let summary = run(None);
+        let manifest_path = summary.folder.join(manifest::MANIFEST_FILE_NAME);
+        let lines = |path: &Path| -> Vec<String> {
+            std::fs::read_to_string(path)
+                .unwrap()
+                .lines()
+                .map(str::to_string)
+                .collect()
+        };
+        let first = lines(&manifest_path);
+        assert_eq!(
+            first[0],
+            "source,outputs,sequence,width,height,status,error,folder"
+        );
+        assert_eq!(
+            first[1],
+            format!("{},01_1.jpg; 01_2.jpg,1,4,2,ok,,", images[0].path.display())
+        );
+        assert!(first[2].starts_with(&format!("{},,2,4,2,failed,", images[1].path.display())));
 
-    let mut jpg_data = Vec::new();
-    let mut encoder = image::codecs::jpeg::JpegEncoder::new_with_quality(&mut jpg_data, 85);
-    encoder.encode_image(&rgb_img)?;
+        image::RgbImage::new(4, 2).save(&images[1].path).unwrap();
+        run(Some(HashSet::from([1])));
+        let retried = lines(&manifest_path);
+        let _ = std::fs::remove_dir_all(&dir);
 
-    let base64_str = encode_to_base64(&jpg_data)?;
-    Ok(base64_str)
-}
+        assert_eq!(retried.len(), 3);
+        assert_eq!(retried[1], first[1]);
+        assert_eq!(
+            retried[2],
+            format!("{},02_1.jpg; 02_2.jpg,2,4,2,ok,,", images[1].path.display())
+        );
+    }
 
-fn encode_to_base64(data: &[u8]) -> Result<String, Box<dyn std::error::Error>> {
-    let mut result = String::new();
-    const TABLE: &[u8] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+    #[test]
+    fn adjust_tone_is_a_no_op_at_zero() {
+        let img =
+            image::DynamicImage::ImageLuma8(image::GrayImage::from_pixel(2, 2, image::Luma([100])));
+        assert!(matches!(
+            adjust_tone(&img, 0, 0),
+            std::borrow::Cow::Borrowed(_)
+        ));
+        assert_eq!(
+            adjust_tone(&img, 20, 0).to_luma8()[(0, 0)],
+            image::Luma([120])
+        );
+    }
 
-    for chunk in data.chunks(3) {
-        let b1 = chunk[0];
-        let b2 = chunk.get(1).copied().unwrap_or(0);
-        let b3 = chunk.get(2).copied().unwrap_or(0);
+    #[test]
+    fn transparency_is_flattened_onto_the_background() {
+        let mut rgba = image::RgbaImage::from_pixel(2, 1, image::Rgba([0, 0, 0, 0]));
+        rgba.put_pixel(1, 0, image::Rgba([0, 0, 0, 255]));
+        let img = image::DynamicImage::ImageRgba8(rgba);
 
-        let n = ((b1 as u32) << 16) | ((b2 as u32) << 8) | (b3 as u32);
+        let flat = flatten_alpha(&img, DEFAULT_BACKGROUND).to_rgb8();
+        assert_eq!(flat[(0, 0)], image::Rgb([255, 255, 255]));
+        assert_eq!(flat[(1, 0)], image::Rgb([0, 0, 0]));
+        let flat = flatten_alpha(&img, [200, 10, 10]).to_rgb8();
+        assert_eq!(flat[(0, 0)], image::Rgb([200, 10, 10]));
 
-        result.push(TABLE[((n >> 18) & 63) as usize] as char);
-        result.push(TABLE[((n >> 12) & 63) as usize] as char);
+        let opaque = image::DynamicImage::new_rgb8(2, 2);
+        assert!(matches!(
+            flatten_alpha(&opaque, DEFAULT_BACKGROUND),
+            std::borrow::Cow::Borrowed(_)
+        ));
 
-        if chunk.len() > 1 {
-            result.push(TABLE[((n >> 6) & 63) as usize] as char);
-        } else {
-            result.push('=');
-        }
+        assert_eq!(hex_color([255, 128, 0]), "#ff8000");
+        assert_eq!(parse_hex_color("#ff8000"), Some([255, 128, 0]));
+        assert_eq!(parse_hex_color("ff8000"), None);
+    }
 
-        if chunk.len() > 2 {
-            result.push(TABLE[(n & 63) as usize] as char);
-        } else {
-            result.push('=');
+    #[test]
+    fn multi_page_tiff_expands_into_one_item_per_page() {
+        let path = std::env::temp_dir().join("irs-multi-page.tif");
+        let mut encoder =
+            tiff::encoder::TiffEncoder::new(std::fs::File::create(&path).unwrap()).unwrap();
+        encoder
+            .write_image::<tiff::encoder::colortype::Gray8>(4, 2, &[10; 8])
+            .unwrap();
+        encoder
+            .write_image::<tiff::encoder::colortype::Gray8>(3, 5, &[200; 15])
+            .unwrap();
+        drop(encoder);
+
+        let sources = expand_pages(path.clone());
+        assert_eq!(
+            sources,
+            vec![(path.clone(), Some(0)), (path.clone(), Some(1))]
+        );
+
+        let info = read_image_info(&path, Some(1), true).unwrap();
+        assert_eq!((info.width, info.height), (3, 5));
+        let page = open_image(&path, Some(1), true).unwrap();
+        assert_eq!(page.to_luma8()[(0, 0)], image::Luma([200]));
+
+        let item = ImageItem {
+            page: Some(1),
+            ..placeholder_item(0, path.clone())
+        };
+        assert_eq!(item_name(&item), "irs-multi-page.tif#2");
+
+        // The same file inside a ZIP input expands into its pages as well
+        let zip_path = std::env::temp_dir().join("irs-multi-page.zip");
+        let mut zip = zip::ZipWriter::new(std::fs::File::create(&zip_path).unwrap());
+        zip.start_file("scans/pages.tif", zip::write::SimpleFileOptions::default())
+            .unwrap();
+        std::io::Write::write_all(&mut zip, &std::fs::read(&path).unwrap()).unwrap();
+        zip.finish().unwrap();
+        let entry = zip_path.join("scans").join("pages.tif");
+        let archived = expand_pages(entry.clone());
+        let page = open_image(&entry, Some(1), true).unwrap();
+        let _ = std::fs::remove_file(&path);
+        let _ = std::fs::remove_file(&zip_path);
+
+        assert_eq!(archived, vec![(entry.clone(), Some(0)), (entry, Some(1))]);
+        assert_eq!(page.to_luma8()[(0, 0)], image::Luma([200]));
+    }
+
+    #[test]
+    fn lightbox_zoom_keeps_the_image_covering_its_frame() {
+        // Zooming in at the right edge keeps that spot under the pointer
+        let view = LightboxView::FIT.zoomed(2.0, 100.0, 0.0, 200.0, 100.0);
+        assert_eq!(view.zoom, 2.0);
+        assert_eq!((view.pan_x, view.pan_y), (-100.0, 0.0));
+
+        // Panning stops once an edge of the image reaches the frame
+        let view = view.panned(500.0, -500.0, 200.0, 100.0);
+        assert_eq!((view.pan_x, view.pan_y), (100.0, -50.0));
+
+        // Zoom is held within range, and back at 1× the image is centred again
+        assert_eq!(
+            view.zoomed(100.0, 0.0, 0.0, 200.0, 100.0).zoom,
+            MAX_LIGHTBOX_ZOOM
+        );
+        assert_eq!(view.zoomed(0.01, 0.0, 0.0, 200.0, 100.0), LightboxView::FIT);
+    }
+
+    #[test]
+    fn large_jpeg_previews_skip_the_full_decode() {
+        let path = std::env::temp_dir().join("irs-large-preview.jpg");
+        image::RgbImage::new(1600, 800).save(&path).unwrap();
+        let options = ThumbnailOptions {
+            size: 100,
+            quality: DEFAULT_THUMBNAIL_QUALITY,
+            auto_orient: true,
+            brightness: 0,
+            contrast: 0,
+            background: DEFAULT_BACKGROUND,
+            large_image_megapixels: 1,
+            fit: PreviewFit::Contain,
+        };
+
+        // 1.28 MP is over the 1 MP threshold, so it's decoded at 1/8 scale
+        let reduced = open_for_thumbnail(&path, None, options).unwrap();
+        let full = open_for_thumbnail(
+            &path,
+            None,
+            ThumbnailOptions {
+                large_image_megapixels: 2,
+                ..options
+            },
+        )
+        .unwrap();
+        let _ = std::fs::remove_file(&path);
+
+        assert_eq!(full.dimensions(), (1600, 800));
+        assert_eq!(reduced.dimensions(), (200, 100));
+    }
+
+    #[test]
+    fn fit_output_only_shrinks() {
+        let img = image::DynamicImage::new_rgb8(400, 200);
+        let options = OutputOptions {
+            limit_size: true,
+            max_dimension: 100,
+            ..OutputOptions::default()
+        };
+        let fitted = fit_output(&img, &options);
+        assert_eq!(fitted.dimensions(), (100, 50));
+
+        let small = image::DynamicImage::new_rgb8(80, 40);
+        assert!(matches!(
+            fit_output(&small, &options),
+            std::borrow::Cow::Borrowed(_)
+        ));
+        let unlimited = OutputOptions::default();
+        assert_eq!(fit_output(&img, &unlimited).dimensions(), (400, 200));
+    }
+
+    #[test]
+    fn trim_borders_crops_to_content() {
+        let mut page = image::RgbImage::from_pixel(20, 10, image::Rgb([255, 255, 255]));
+        for x in 4..15 {
+            for y in 2..8 {
+                page.put_pixel(x, y, image::Rgb([0, 0, 0]));
+            }
         }
+        let trimmed = trim_borders(image::DynamicImage::ImageRgb8(page), DEFAULT_TRIM_THRESHOLD);
+        assert_eq!(trimmed.dimensions(), (11, 6));
+
+        // Nothing to keep, so a blank page is left alone
+        let blank =
+            image::DynamicImage::ImageLuma8(image::GrayImage::from_pixel(5, 5, image::Luma([250])));
+        assert_eq!(
+            trim_borders(blank, DEFAULT_TRIM_THRESHOLD).dimensions(),
+            (5, 5)
+        );
     }
 
-    Ok(result)
-}
+    #[test]
+    fn dry_run_plan_follows_numbering_and_rotation() {
+        let item = |id: usize, rotation: u16| ImageItem {
+            id,
+            path: PathBuf::from(format!("scan{}.jpg", id)),
+            page: None,
+            thumbnail_base64: String::new(),
+            rotation,
+            split_ratio: DEFAULT_SPLIT_RATIO,
+            split_mode: None,
+            quality: None,
+            dpi: None,
+            width: 400,
+            height: 1,
+            file_size: 0,
+            modified: None,
+            captured: None,
+            orientation: 1,
+        };
+        let options = OutputOptions {
+            prefix: "CH_".to_string(),
+            start_index: 7,
+            padding: 3,
+            ..OutputOptions::default()
+        };
+        let spl_folder = std::env::temp_dir().join("irs-dry-run-missing-folder");
 
-fn process_single_image(
-    item: &ImageItem,
-    spl_folder: &PathBuf,
-    sequence_num: usize,
-) -> Result<(), Box<dyn std::error::Error>> {
-    let img = image::open(&item.path)?;
+        // The rotated image is only one pixel wide, so it can't be split vertically
+        let plan = plan_output_names(&[item(0, 0), item(1, 90)], &spl_folder, &options);
+        assert_eq!(
+            plan,
+            vec![
+                "scan0.jpg → CH_007_1.jpg, CH_007_2.jpg".to_string(),
+                "scan1.jpg → CH_008_1.jpg".to_string(),
+            ]
+        );
 
-    let (width, height) = img.dimensions();
-    let half_width = width / 2;
+        // Keeping the original adds part 0 ahead of the pieces
+        let options = OutputOptions {
+            keep_original: true,
+            ..options
+        };
+        let plan = plan_output_names(&[item(0, 0)], &spl_folder, &options);
+        assert_eq!(
+            plan,
+            vec!["scan0.jpg → CH_007_0.jpg, CH_007_1.jpg, CH_007_2.jpg".to_string()]
+        );
 
-    let left_half = img.crop_imm(0, 0, half_width, height);
-    let right_half = img.crop_imm(half_width, 0, half_width, height);
+        // Renaming only writes one unsuffixed file per image
+        let options = OutputOptions {
+            rename_only: true,
+            ..options
+        };
+        let plan = plan_output_names(&[item(0, 0)], &spl_folder, &options);
+        assert_eq!(plan, vec!["scan0.jpg → CH_007.jpg".to_string()]);
 
-    let left_path = spl_folder.join(format!("{}_{}.jpg", pad_number(sequence_num), "1"));
-    let right_path = spl_folder.join(format!("{}_{}.jpg", pad_number(sequence_num), "2"));
+        // With landscape-only splitting the portrait image passes through whole
+        let options = OutputOptions {
+            rename_only: false,
+            keep_original: false,
+            landscape_only: true,
+            ..options
+        };
+        let portrait = ImageItem {
+            width: 300,
+            height: 400,
+            ..item(1, 0)
+        };
+        let plan = plan_output_names(&[item(0, 0), portrait], &spl_folder, &options);
+        assert_eq!(
+            plan,
+            vec![
+                "scan0.jpg → CH_007_1.jpg, CH_007_2.jpg".to_string(),
+                "scan1.jpg → CH_008_1.jpg".to_string(),
+            ]
+        );
+    }
 
-    save_with_dpi(&left_half, &left_path, 100)?;
-    save_with_dpi(&right_half, &right_path, 100)?;
+    #[test]
+    fn two_way_split_cuts_at_ratio() {
+        let img = image::DynamicImage::new_rgb8(11, 4);
+        let widths = |ratio: f32| -> Vec<u32> {
+            split_image(&img, SplitMode::Vertical, 2, ratio, 0, false)
+                .iter()
+                .map(|piece| piece.width())
+                .collect()
+        };
+        // The default matches the old centre split, with the odd column on the right
+        assert_eq!(widths(DEFAULT_SPLIT_RATIO), vec![5, 6]);
+        assert_eq!(widths(0.7), vec![7, 4]);
+        // Neither side may be empty
+        assert_eq!(widths(0.0), vec![1, 10]);
+        assert_eq!(widths(1.0), vec![10, 1]);
 
-    Ok(())
-}
+        // Overlap widens both halves across the cut but never past the image edges
+        let overlapped: Vec<u32> =
+            split_image(&img, SplitMode::Vertical, 2, DEFAULT_SPLIT_RATIO, 2, false)
+                .iter()
+                .map(|piece| piece.width())
+                .collect();
+        assert_eq!(overlapped, vec![7, 8]);
+        let overlapped: Vec<u32> =
+            split_image(&img, SplitMode::Vertical, 2, DEFAULT_SPLIT_RATIO, 50, false)
+                .iter()
+                .map(|piece| piece.width())
+                .collect();
+        assert_eq!(overlapped, vec![11, 11]);
+    }
 
-fn save_with_dpi(
-    img: &image::DynamicImage,
-    path: &PathBuf,
-    quality: u8,
-) -> Result<(), Box<dyn std::error::Error>> {
-    // Encode image into an in-memory JPEG buffer first
-    let mut jpg_buf: Vec<u8> = Vec::new();
-    {
-        let mut encoder = image::codecs::jpeg::JpegEncoder::new_with_quality(&mut jpg_buf, quality);
-        let rgb_image = img.to_rgb8();
-        encoder.encode_image(&rgb_image)?;
+    #[test]
+    fn rubber_band_selects_the_cards_it_touches() {
+        use dioxus::html::geometry::euclid::{Point2D, Rect, Size2D};
+        let card = |x: f64, y: f64| Rect::new(Point2D::new(x, y), Size2D::new(100.0, 100.0));
+        let mut band = RubberBand {
+            start: (250.0, 50.0),
+            end: (252.0, 52.0),
+            origin: (0.0, 0.0),
+            cards: vec![
+                (0, card(0.0, 0.0)),
+                (1, card(120.0, 0.0)),
+                (2, card(0.0, 120.0)),
+            ],
+            base: HashSet::from([7]),
+        };
+        assert!(!band.dragged());
+
+        // Dragged up and to the left from the gap right of card 1, across cards 0 and 1
+        band.end = (60.0, 10.0);
+        assert!(band.dragged());
+        assert_eq!(band.selection(), HashSet::from([0, 1, 7]));
     }
 
-    // Ensure JFIF APP0 segment sets DPI (units = inch, X/Y density)
-    set_jpeg_dpi(&mut jpg_buf, 300)?;
+    #[test]
+    fn grid_columns_fill_the_width() {
+        // Four 150px cards plus three 12px gaps need 636px
+        assert_eq!(grid_columns(636.0, 150), 4);
+        assert_eq!(grid_columns(635.0, 150), 3);
+        // A very narrow window still shows one column
+        assert_eq!(grid_columns(40.0, 150), 1);
+    }
 
-    // Write bytes to file
-    std::fs::write(path, &jpg_buf)?;
-    Ok(())
-}
+    #[test]
+    fn name_templates_are_checked_and_expanded() {
+        assert!(validate_name_template("").is_ok());
+        assert!(validate_name_template("{orig}_{side}").is_ok());
+        assert!(validate_name_template("{seq}-{width}px").is_ok());
+        assert!(validate_name_template("{side}").is_err());
+        assert!(validate_name_template("{seq}_{part}").is_err());
+        assert!(validate_name_template("{seq").is_err());
+        assert!(validate_name_template("seq}{seq}").is_err());
+        assert!(validate_name_template("{seq}/{side}").is_err());
 
-// Find JFIF APP0 segment and set units and X/Y density. If not present, insert one after SOI.
-fn set_jpeg_dpi(buf: &mut Vec<u8>, dpi: u16) -> Result<(), Box<dyn std::error::Error>> {
-    // Validate JPEG SOI
-    if buf.len() < 4 || buf[0] != 0xFF || buf[1] != 0xD8 {
-        return Err("Not a valid JPEG".into());
+        let item = ImageItem {
+            width: 1200,
+            height: 800,
+            rotation: 90,
+            page: Some(1),
+            ..placeholder_item(0, PathBuf::from("/scans/ch{side}.tif"))
+        };
+        let options = OutputOptions {
+            prefix: "CH03_".to_string(),
+            name_template: "{orig}_{seq}_{side}_{width}".to_string(),
+            ..OutputOptions::default()
+        };
+        // The braces in the file's own name aren't taken for a token
+        assert_eq!(
+            output_file_name(&options, &item, 7, 2),
+            "CH03_ch{side}_p2_07_2_800.jpg"
+        );
     }
 
-    // Walk segments starting after SOI
-    let mut i = 2usize; // start after SOI
-    while i + 4 <= buf.len() {
-        if buf[i] != 0xFF {
-            break;
-        }
-        let marker = buf.get(i + 1).copied().unwrap_or(0);
-        // Start of Scan (0xDA) — image data starts, stop searching
-        if marker == 0xDA {
-            break;
-        }
-        // Ensure we can read length
-        if i + 4 > buf.len() {
-            break;
+    #[test]
+    fn subfolder_name_must_be_a_plain_component() {
+        assert!(validate_subfolder_name("SPL").is_ok());
+        assert!(validate_subfolder_name("  ").is_err());
+        assert!(validate_subfolder_name("..").is_err());
+        assert!(validate_subfolder_name("a/b").is_err());
+    }
+
+    #[test]
+    fn chunks_cover_every_image_with_at_most_one_per_worker() {
+        for (total, workers) in [(10, 4), (3, 8), (100, 1), (1, 1), (0, 4)] {
+            let size = chunk_size(total, workers);
+            assert!(total.div_ceil(size) <= workers);
+            assert!(size * workers >= total);
         }
-        let len = ((buf[i + 2] as usize) << 8) | (buf[i + 3] as usize);
-        if len < 2 {
-            break;
+    }
+
+    // Units and X/Y density of the first JFIF APP0 in `buf`, read the way a viewer would
+    fn jfif_density(buf: &[u8]) -> Option<(u8, u16, u16)> {
+        let mut i = 2;
+        while i + 4 <= buf.len() && buf[i] == 0xFF && buf[i + 1] != 0xDA {
+            let len = u16::from_be_bytes([buf[i + 2], buf[i + 3]]) as usize;
+            if buf[i + 1] == 0xE0 && buf[i + 4..].starts_with(b"JFIF\0") {
+                let density = |at: usize| u16::from_be_bytes([buf[i + at], buf[i + at + 1]]);
+                return Some((buf[i + 11], density(12), density(14)));
+            }
+            i += 2 + len;
         }
-        // APP0 marker is 0xE0
-        if marker == 0xE0 {
-            // Check for "JFIF\0" identifier at i+4..i+9
-            if i + 4 + 5 <= buf.len() {
-                if &buf[i + 4..i + 9] == b"JFIF\0" {
-                    // units at offset i+11, xdensity at i+12..13, ydensity at i+14..15
-                    if i + 15 < buf.len() {
-                        let units_pos = i + 11;
-                        let x_pos = i + 12;
-                        buf[units_pos] = 1; // dots per inch
-                        buf[x_pos] = (dpi >> 8) as u8;
-                        buf[x_pos + 1] = (dpi & 0xFF) as u8;
-                        buf[x_pos + 2] = (dpi >> 8) as u8;
-                        buf[x_pos + 3] = (dpi & 0xFF) as u8;
-                        return Ok(());
-                    }
-                }
-            }
-        }
-        // move to next segment: marker(2) + length bytes
-        i += 2 + len;
-    }
-
-    // If no JFIF APP0 found — insert one right after SOI (offset 2)
-    // Build APP0 JFIF segment (length = 16 -> 0x0010)
-    let mut app0: Vec<u8> = Vec::new();
-    app0.push(0xFF);
-    app0.push(0xE0);
-    app0.push(0x00);
-    app0.push(0x10); // length 16
-    app0.extend_from_slice(b"JFIF\0"); // identifier
-    app0.push(0x01); // version major
-    app0.push(0x02); // version minor
-    app0.push(0x01); // units = dots per inch
-    app0.push((dpi >> 8) as u8);
-    app0.push((dpi & 0xFF) as u8);
-    app0.push((dpi >> 8) as u8);
-    app0.push((dpi & 0xFF) as u8);
-    app0.push(0x00); // Xthumbnail
-    app0.push(0x00); // Ythumbnail
-
-    // Insert after SOI (position 2)
-    buf.splice(2..2, app0.iter().cloned());
+        None
+    }
 
-    Ok(())
-}
+    // `buf` with its 18-byte JFIF APP0 swapped for `app0`
+    fn with_app0(mut buf: Vec<u8>, app0: &[u8]) -> Vec<u8> {
+        assert_eq!(&buf[2..4], &[0xFF, 0xE0]);
+        buf.splice(2..20, app0.iter().copied());
+        buf
+    }
+
+    #[test]
+    fn jpeg_dpi_round_trips_through_a_real_encode() {
+        let img = image::DynamicImage::new_rgb8(6, 4);
+        let mut buf = encode_jpeg(&img, 90, 300, DEFAULT_BACKGROUND).unwrap();
+        assert_eq!(jfif_density(&buf), Some((1, 300, 300)));
 
-fn process_images_sync(images: Vec<ImageItem>, save_folder: PathBuf) -> Result<usize, String> {
-    // Synchronous version of the threaded processing. Returns number of images processed or Err(msg).
-    let spl_folder = save_folder.join("SPL");
-    if let Err(e) = std::fs::create_dir_all(&spl_folder) {
-        return Err(format!("Failed to create output folder: {}", e));
+        // Patched in place the second time, not given another APP0
+        let len = buf.len();
+        set_jpeg_dpi(&mut buf, 1200).unwrap();
+        assert_eq!(buf.len(), len);
+        assert_eq!(jfif_density(&buf), Some((1, 1200, 1200)));
+        let decoded = image::load_from_memory_with_format(&buf, ImageFormat::Jpeg).unwrap();
+        assert_eq!(decoded.dimensions(), (6, 4));
+
+        // Without any APP0, one is inserted after SOI
+        let mut bare = with_app0(buf, &[]);
+        assert_eq!(jfif_density(&bare), None);
+        set_jpeg_dpi(&mut bare, 72).unwrap();
+        assert_eq!(jfif_density(&bare), Some((1, 72, 72)));
+        assert!(image::load_from_memory(&bare).is_ok());
     }
 
-    let (tx, rx) = std::sync::mpsc::channel();
-    let images_arc = std::sync::Arc::new(images);
-    let spl_folder_arc = std::sync::Arc::new(spl_folder);
-    let mut handles = Vec::new();
+    #[test]
+    fn jpeg_dpi_is_written_into_app0_segments_of_any_length() {
+        let img = image::DynamicImage::new_rgb8(6, 4);
+        let encoded = encode_jpeg(&img, 90, 300, DEFAULT_BACKGROUND).unwrap();
+        let jfif = |length: u8, version: u8, units: u8, thumbnail: &[u8]| {
+            let mut app0 = vec![0xFF, 0xE0, 0x00, length];
+            app0.extend_from_slice(b"JFIF\0");
+            app0.extend_from_slice(&[version, 2, units, 0, 1, 0, 1]);
+            app0.extend_from_slice(thumbnail);
+            app0
+        };
 
-    let chunk_size = 3;
-    let total_images = images_arc.len();
-    let mut image_num = 1usize;
+        // A 2 × 1 RGB thumbnail after the header is left as it is
+        let thumbnail = [2, 1, 10, 20, 30, 40, 50, 60];
+        let mut buf = with_app0(encoded.clone(), &jfif(22, 1, 0, &thumbnail));
+        set_jpeg_dpi(&mut buf, 600).unwrap();
+        assert_eq!(jfif_density(&buf), Some((1, 600, 600)));
+        assert_eq!(&buf[18..26], &thumbnail);
+        assert!(image::load_from_memory(&buf).is_ok());
 
-    for chunk in images_arc.chunks(chunk_size) {
-        let chunk_clone = chunk.to_vec();
-        let chunk_len = chunk_clone.len();
-        let tx = tx.clone();
-        let spl_folder = std::sync::Arc::clone(&spl_folder_arc);
-        let start_num = image_num;
+        // An unknown version or unit, or a header cut short, gets a fresh APP0 in its place
+        for app0 in [
+            jfif(22, 2, 0, &thumbnail),
+            jfif(22, 1, 9, &thumbnail),
+            jfif(14, 1, 1, &[]),
+        ] {
+            let mut buf = with_app0(encoded.clone(), &app0);
+            set_jpeg_dpi(&mut buf, 150).unwrap();
+            assert_eq!(jfif_density(&buf), Some((1, 150, 150)));
+            assert_eq!(buf.len(), encoded.len());
+            assert!(image::load_from_memory(&buf).is_ok());
+        }
 
-        let handle = std::thread::spawn(move || {
-            for (idx, item) in chunk_clone.iter().enumerate() {
-                let current_num = start_num + idx;
-                match process_single_image(item, &spl_folder, current_num) {
-                    Ok(_) => {
-                        let _ = tx.send(format!(
-                            "✓ {}",
-                            item.path.file_name().unwrap_or_default().to_string_lossy()
-                        ));
-                    }
-                    Err(_) => {
-                        let _ = tx.send(format!(
-                            "✗ {}",
-                            item.path.file_name().unwrap_or_default().to_string_lossy()
-                        ));
-                    }
-                }
-            }
-        });
+        // A length running past the end of the data is refused rather than read
+        let mut truncated = with_app0(encoded, &[0xFF, 0xE0, 0xFF, 0xFF]);
+        truncated.truncate(64);
+        assert!(matches!(
+            set_jpeg_dpi(&mut truncated, 300),
+            Err(IrsError::InvalidJpeg(_))
+        ));
+    }
 
-        image_num += chunk_len;
-        handles.push(handle);
+    #[test]
+    fn image_overrides_win_over_the_batch_quality_and_dpi() {
+        let (dir, mut images) = png_batch("output-override", 2);
+        images[1].dpi = Some(600);
+        let options = OutputOptions::default();
+        let overridden = item_options(
+            &ImageItem {
+                quality: Some(60),
+                ..images[1].clone()
+            },
+            &options,
+        );
+        assert_eq!((overridden.quality, overridden.dpi), (60, 600));
+        // A value from a project file that the menu doesn't list is still offered
+        assert_eq!(override_choices(DPI_OPTIONS, Some(600)), DPI_OPTIONS);
+        assert_eq!(
+            override_choices(QUALITY_OVERRIDE_OPTIONS, Some(1)).last(),
+            Some(&1)
+        );
+        assert!(matches!(
+            item_options(&images[0], &options),
+            std::borrow::Cow::Borrowed(_)
+        ));
+
+        let summary = process_images_sync(
+            images,
+            None,
+            dir.join("out"),
+            options,
+            std::sync::Arc::new(AtomicBool::new(false)),
+            |_, _, _| {},
+        )
+        .unwrap();
+        let density = |name: &str| jfif_density(&std::fs::read(summary.folder.join(name)).unwrap());
+        let (follows, own) = (density("01_1.jpg"), density("02_2.jpg"));
+        let _ = std::fs::remove_dir_all(&dir);
+
+        assert_eq!(follows, Some((1, DEFAULT_DPI, DEFAULT_DPI)));
+        assert_eq!(own, Some((1, 600, 600)));
     }
 
-    drop(tx);
+    #[test]
+    fn icc_profile_segments_follow_jfif() {
+        let img = image::DynamicImage::new_rgb8(2, 2);
+        let mut buf = encode_jpeg(&img, 90, 300, DEFAULT_BACKGROUND).unwrap();
+        // Big enough to need two APP2 segments
+        let profile = vec![7u8; 70_000];
+        set_jpeg_icc_profile(&mut buf, &profile).unwrap();
 
-    // Collect results (this will block until all senders are dropped)
-    let mut _results: Vec<String> = Vec::new();
-    for msg in rx.iter() {
-        _results.push(msg);
+        // SOI, then the 18-byte JFIF APP0, then the first profile segment
+        assert_eq!(&buf[2..4], &[0xFF, 0xE0]);
+        assert_eq!(&buf[20..22], &[0xFF, 0xE2]);
+        assert_eq!(&buf[24..36], b"ICC_PROFILE\0");
+        assert_eq!(&buf[36..38], &[1, 2]);
+        assert!(image::load_from_memory(&buf).is_ok());
     }
 
-    // Join threads
-    for handle in handles {
-        let _ = handle.join();
+    #[test]
+    fn comment_segment_follows_the_app_segments() {
+        let img = image::DynamicImage::new_rgb8(2, 2);
+        let mut buf = encode_jpeg(&img, 90, 300, DEFAULT_BACKGROUND).unwrap();
+        set_jpeg_icc_profile(&mut buf, &[7u8; 10]).unwrap();
+        let item = placeholder_item(0, PathBuf::from("/scans/scan012.tif"));
+        let options = OutputOptions {
+            embed_comment: true,
+            ..OutputOptions::default()
+        };
+        let comment = jpeg_comment(&options, &item).unwrap();
+        assert_eq!(comment, "Processed by IRS; source: scan012.tif");
+        set_jpeg_comment(&mut buf, &comment).unwrap();
+
+        // SOI, the 18-byte JFIF APP0 and the 28-byte ICC APP2, then the comment
+        let at = 2 + 18 + 28;
+        assert_eq!(&buf[at..at + 2], &[0xFF, 0xFE]);
+        assert_eq!(&buf[at + 4..at + 4 + comment.len()], comment.as_bytes());
+        assert!(image::load_from_memory(&buf).is_ok());
+        assert_eq!(jpeg_comment(&OutputOptions::default(), &item), None);
     }
 
-    Ok(total_images)
-}
+    #[test]
+    fn png_dpi_chunk_follows_ihdr() {
+        // The CRC of a bare IEND chunk is a well-known constant
+        assert_eq!(crc32(b"IEND"), 0xAE42_6082);
+
+        let img = image::DynamicImage::new_rgb8(2, 2);
+        let mut buf = Vec::new();
+        img.write_to(&mut std::io::Cursor::new(&mut buf), ImageFormat::Png)
+            .unwrap();
+        set_png_dpi(&mut buf, 300).unwrap();
+
+        assert_eq!(&buf[37..41], b"pHYs");
+        // 300 DPI is 11811 pixels per metre
+        assert_eq!(&buf[41..45], &11811u32.to_be_bytes());
+        assert!(image::load_from_memory(&buf).is_ok());
+    }
+
+    #[test]
+    fn project_round_trip_pulls_values_into_range() {
+        let path = std::env::temp_dir().join("irs-project-round-trip.json");
+        let item = |rotation, split_ratio| project::ProjectItem {
+            path: PathBuf::from(format!("/scans/{rotation}.jpg")),
+            page: None,
+            rotation,
+            split_ratio,
+            split_mode: None,
+            quality: None,
+            dpi: None,
+        };
+        let project = project::Project {
+            version: 1,
+            output: OutputOptions {
+                quality: 0,
+                dpi: 0,
+                ..OutputOptions::default()
+            },
+            items: vec![
+                item(90, 0.3),
+                project::ProjectItem {
+                    quality: Some(0),
+                    dpi: Some(600),
+                    ..item(45, 2.0)
+                },
+                project::ProjectItem {
+                    dpi: Some(0),
+                    ..item(180, 0.5)
+                },
+            ],
+        };
+        project.save(&path).unwrap();
+        let loaded = project::Project::load(&path).unwrap();
+        let _ = std::fs::remove_file(&path);
 
-fn pad_number(num: usize) -> String {
-    format!("{:02}", num)
+        assert_eq!(loaded.output.quality, 1);
+        assert_eq!(loaded.output.dpi, DEFAULT_DPI);
+        assert_eq!(loaded.items[0], item(90, 0.3));
+        // Values a hand edit could produce snap back to what the UI allows, keeping the order
+        assert_eq!(loaded.items[1].path, PathBuf::from("/scans/45.jpg"));
+        assert_eq!(loaded.items[1].rotation, 0);
+        assert_eq!(loaded.items[1].split_ratio, MAX_SPLIT_RATIO);
+        assert_eq!(loaded.items[1].quality, Some(1));
+        assert_eq!(loaded.items[1].dpi, Some(600));
+        assert_eq!(loaded.items[2].dpi, None);
+    }
+
+    #[test]
+    fn recent_batches_are_newest_first_without_repeats() {
+        let (dir, _) = png_batch("recent", 0);
+        png_item(0, dir.join("vol1").join("p1.png"));
+        let folder = RecentBatch::Folder {
+            path: dir.join("vol1"),
+            recursive: false,
+        };
+        let files = RecentBatch::Files(vec![dir.join("vol1").join("p1.png"), dir.join("p2.png")]);
+        let gone = RecentBatch::Archive(dir.join("old.zip"));
+
+        let mut settings = Settings::default();
+        for idx in 0..12 {
+            settings.remember(RecentBatch::Files(vec![dir.join(format!("{idx}.png"))]));
+        }
+        settings.remember(folder.clone());
+        settings.remember(files.clone());
+        // Opening a listed batch again moves it to the top instead of listing it twice
+        settings.remember(folder.clone());
+        settings.remember(gone.clone());
+        let listed = folder.image_paths().unwrap();
+        let (exists, labels) = (
+            [&folder, &files, &gone].map(RecentBatch::exists),
+            [&folder, &files, &gone].map(RecentBatch::label),
+        );
+        let _ = std::fs::remove_dir_all(&dir);
+
+        assert_eq!(settings.recent.len(), recent::MAX_RECENT);
+        assert_eq!(
+            &settings.recent[..3],
+            &[gone.clone(), folder.clone(), files]
+        );
+        assert_eq!(listed, vec![dir.join("vol1").join("p1.png")]);
+        assert_eq!(exists, [true, true, false]);
+        assert_eq!(labels, ["vol1/", "p1.png + 1 more", "old.zip"]);
+        assert_eq!(folder.save_folder(), dir.join("vol1"));
+        assert_eq!(gone.save_folder(), dir);
+    }
 }
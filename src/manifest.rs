@@ -0,0 +1,115 @@
+use std::path::Path;
+
+// Written into the output folder next to the pieces
+pub const MANIFEST_FILE_NAME: &str = "manifest.csv";
+
+const HEADER: &str = "source,outputs,sequence,width,height,status,error,folder";
+
+// One source image and what became of it
+pub struct ManifestRow {
+    // Full path, with `#n` for a page of a multi-page TIFF
+    pub source: String,
+    // File names written for it, plus its pages in the PDF if one was made
+    pub outputs: Vec<String>,
+    pub sequence: usize,
+    // Size as split, i.e. after the user's rotation
+    pub width: u32,
+    pub height: u32,
+    // Why the image failed; None when it was written
+    pub error: Option<String>,
+    // Subfolder the outputs went into when source folders are mirrored, otherwise empty
+    pub folder: String,
+}
+
+// Write `rows` in sequence order, one line per source image, grouped by subfolder. With `merge`,
+// rows already in the file for other images are kept, so retrying a few failed images updates
+// their rows instead of dropping everyone else's. An image is told apart by its subfolder and
+// sequence number, since numbering can restart in each subfolder.
+pub fn write_manifest(path: &Path, rows: Vec<ManifestRow>, merge: bool) -> std::io::Result<()> {
+    let existing = if merge {
+        std::fs::read_to_string(path).ok()
+    } else {
+        None
+    };
+    std::fs::write(path, format_manifest(rows, existing.as_deref()))
+}
+
+// The CSV text of `rows`, merged with the rows of an `existing` manifest as `write_manifest` does
+pub fn format_manifest(rows: Vec<ManifestRow>, existing: Option<&str>) -> String {
+    let mut lines: Vec<((String, usize), String)> = rows
+        .iter()
+        .map(|row| ((row.folder.clone(), row.sequence), format_row(row)))
+        .collect();
+
+    if let Some(existing) = existing {
+        let kept = existing.lines().skip(1).filter_map(|line| {
+            let fields = split_fields(line);
+            let sequence = fields.get(2)?.parse::<usize>().ok()?;
+            // Files written before the folder column existed have it missing
+            let folder = fields.get(7).cloned().unwrap_or_default();
+            let replaced = rows
+                .iter()
+                .any(|row| row.sequence == sequence && row.folder == folder);
+            (!replaced).then(|| ((folder, sequence), line.to_string()))
+        });
+        lines.extend(kept);
+    }
+    lines.sort_by(|(a, _), (b, _)| a.cmp(b));
+
+    let mut csv = String::from(HEADER);
+    csv.push('\n');
+    for (_, line) in lines {
+        csv.push_str(&line);
+        csv.push('\n');
+    }
+    csv
+}
+
+fn format_row(row: &ManifestRow) -> String {
+    let fields = [
+        row.source.clone(),
+        row.outputs.join("; "),
+        row.sequence.to_string(),
+        row.width.to_string(),
+        row.height.to_string(),
+        if row.error.is_some() { "failed" } else { "ok" }.to_string(),
+        row.error.clone().unwrap_or_default(),
+        row.folder.clone(),
+    ];
+    fields
+        .iter()
+        .map(|field| escape(field))
+        .collect::<Vec<_>>()
+        .join(",")
+}
+
+// Quote fields that need it. Line breaks become spaces so every record stays on one line.
+fn escape(field: &str) -> String {
+    let field = field.replace(['\r', '\n'], " ");
+    if field.contains([',', '"']) {
+        format!("\"{}\"", field.replace('"', "\"\""))
+    } else {
+        field
+    }
+}
+
+// Fields of one line as written by `format_row`
+fn split_fields(line: &str) -> Vec<String> {
+    let mut fields = Vec::new();
+    let mut field = String::new();
+    let mut quoted = false;
+    let mut chars = line.chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            '"' if quoted && chars.peek() == Some(&'"') => {
+                field.push('"');
+                chars.next();
+            }
+            '"' => quoted = !quoted,
+            ',' if !quoted => fields.push(std::mem::take(&mut field)),
+            _ => field.push(c),
+        }
+    }
+    fields.push(field);
+    fields
+}
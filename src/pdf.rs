@@ -0,0 +1,75 @@
+use crate::error::IrsError;
+use printpdf::{
+    ColorBits, ColorSpace, Image, ImageFilter, ImageTransform, ImageXObject, Mm, PdfDocument, Px,
+};
+use std::io::Write;
+
+const MM_PER_INCH: f32 = 25.4;
+
+// One split piece, already JPEG-compressed so a long batch doesn't hold raw pixels in memory
+pub struct PdfPage {
+    pub jpeg: Vec<u8>,
+    pub width: u32,
+    pub height: u32,
+    // Single-channel JPEG
+    pub grayscale: bool,
+    // Resolution the page is sized at; an image can override the batch's DPI
+    pub dpi: u16,
+}
+
+// Write `pages` in order as a single PDF. Each page is sized from its pixel dimensions at its DPI,
+// so printing it at 100% reproduces the scan at its physical size.
+pub fn write_pdf(out: impl Write, title: &str, pages: Vec<PdfPage>) -> Result<(), IrsError> {
+    let page_size = |px: u32, dpi: u16| Mm(px as f32 / dpi.max(1) as f32 * MM_PER_INCH);
+
+    let mut pages = pages.into_iter();
+    let first = pages
+        .next()
+        .ok_or_else(|| IrsError::encode("No pages to write"))?;
+    let (doc, first_page, first_layer) = PdfDocument::new(
+        title,
+        page_size(first.width, first.dpi),
+        page_size(first.height, first.dpi),
+        "Page",
+    );
+    add_image(doc.get_page(first_page).get_layer(first_layer), first);
+
+    for page in pages {
+        let (page_index, layer_index) = doc.add_page(
+            page_size(page.width, page.dpi),
+            page_size(page.height, page.dpi),
+            "Page",
+        );
+        add_image(doc.get_page(page_index).get_layer(layer_index), page);
+    }
+
+    doc.save(&mut std::io::BufWriter::new(out))
+        .map_err(IrsError::encode)
+}
+
+fn add_image(layer: printpdf::PdfLayerReference, page: PdfPage) {
+    let dpi = page.dpi.max(1) as f32;
+    // The JPEG bytes are embedded as-is with the DCT filter instead of being re-encoded
+    let image = Image::from(ImageXObject {
+        width: Px(page.width as usize),
+        height: Px(page.height as usize),
+        color_space: if page.grayscale {
+            ColorSpace::Greyscale
+        } else {
+            ColorSpace::Rgb
+        },
+        bits_per_component: ColorBits::Bit8,
+        interpolate: true,
+        image_data: page.jpeg,
+        image_filter: Some(ImageFilter::DCT),
+        smask: None,
+        clipping_bbox: None,
+    });
+    image.add_to_layer(
+        layer,
+        ImageTransform {
+            dpi: Some(dpi),
+            ..Default::default()
+        },
+    );
+}
@@ -0,0 +1,94 @@
+use crate::settings::Settings;
+use crate::{clamp_quality, ImageItem, OutputOptions, SplitMode, MAX_SPLIT_RATIO, MIN_SPLIT_RATIO};
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+
+// File extension offered by the save and open dialogs
+pub const PROJECT_EXTENSION: &str = "json";
+
+// Bump when the layout changes in a way older files can't be read as
+const PROJECT_VERSION: u32 = 1;
+
+// An arranged set of images and the output settings it was arranged for, saved as JSON.
+// Items are stored in display order; thumbnails are regenerated when the project is opened.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Project {
+    pub version: u32,
+    pub output: OutputOptions,
+    pub items: Vec<ProjectItem>,
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct ProjectItem {
+    pub path: PathBuf,
+    // Page of a multi-page TIFF, missing for ordinary files
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub page: Option<usize>,
+    #[serde(default)]
+    pub rotation: u16,
+    #[serde(default = "default_split_ratio")]
+    pub split_ratio: f32,
+    // Per-image split mode, missing when the image follows the project's setting
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub split_mode: Option<SplitMode>,
+    // Per-image JPEG quality and DPI, missing when the image follows the project's settings
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub quality: Option<u8>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub dpi: Option<u16>,
+}
+
+fn default_split_ratio() -> f32 {
+    crate::DEFAULT_SPLIT_RATIO
+}
+
+impl Project {
+    pub fn new(images: &[ImageItem], output: &OutputOptions) -> Self {
+        Self {
+            version: PROJECT_VERSION,
+            output: output.clone(),
+            items: images
+                .iter()
+                .map(|img| ProjectItem {
+                    path: img.path.clone(),
+                    page: img.page,
+                    rotation: img.rotation,
+                    split_ratio: img.split_ratio,
+                    split_mode: img.split_mode,
+                    quality: img.quality,
+                    dpi: img.dpi,
+                })
+                .collect(),
+        }
+    }
+
+    pub fn save(&self, path: &Path) -> Result<(), Box<dyn std::error::Error>> {
+        std::fs::write(path, serde_json::to_string_pretty(self)?)?;
+        Ok(())
+    }
+
+    pub fn load(path: &Path) -> Result<Self, Box<dyn std::error::Error>> {
+        let project: Project = serde_json::from_str(&std::fs::read_to_string(path)?)?;
+        if project.version > PROJECT_VERSION {
+            return Err("Project was saved by a newer version of IRS".into());
+        }
+        Ok(project.sanitized())
+    }
+
+    // Like the settings file, a project can be edited by hand, so pull values back into range
+    fn sanitized(mut self) -> Self {
+        let settings = Settings {
+            output: self.output,
+            ..Settings::default()
+        };
+        self.output = settings.sanitized().output;
+        for item in &mut self.items {
+            item.rotation = item.rotation / 90 % 4 * 90;
+            item.split_ratio = item.split_ratio.clamp(MIN_SPLIT_RATIO, MAX_SPLIT_RATIO);
+            item.quality = item.quality.map(|quality| clamp_quality(quality as i64));
+            // A density of 0 would be written into the files as is, so it falls back to the batch's
+            item.dpi = item.dpi.filter(|&dpi| dpi > 0);
+        }
+        self
+    }
+}
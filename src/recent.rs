@@ -0,0 +1,78 @@
+use crate::{archive, scan};
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+
+// Entries kept in the RECENT list; opening another drops the oldest
+pub const MAX_RECENT: usize = 10;
+
+// A batch opened with OPEN, FOLDER or ZIP, remembered so it can be opened again in one click.
+// Folders and archives are read afresh on reopening, so images added since are picked up.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub enum RecentBatch {
+    Files(Vec<PathBuf>),
+    Folder { path: PathBuf, recursive: bool },
+    Archive(PathBuf),
+}
+
+impl RecentBatch {
+    // How the batch is listed, e.g. `scans/`, `batch.zip` or `p001.jpg + 11 more`
+    pub fn label(&self) -> String {
+        let name = |path: &Path| {
+            path.file_name()
+                .map(|name| name.to_string_lossy().to_string())
+                .unwrap_or_else(|| path.display().to_string())
+        };
+        match self {
+            RecentBatch::Files(paths) => match paths.first() {
+                Some(first) if paths.len() > 1 => {
+                    format!("{} + {} more", name(first), paths.len() - 1)
+                }
+                Some(first) => name(first),
+                None => String::new(),
+            },
+            RecentBatch::Folder { path, .. } => format!("{}/", name(path)),
+            RecentBatch::Archive(path) => name(path),
+        }
+    }
+
+    // Full path shown on hover
+    pub fn location(&self) -> String {
+        match self {
+            RecentBatch::Files(_) => self.save_folder().display().to_string(),
+            RecentBatch::Folder { path, .. } | RecentBatch::Archive(path) => {
+                path.display().to_string()
+            }
+        }
+    }
+
+    // Whether there's still something to open. Files count while any of them is left; the missing
+    // ones are reported when the batch is loaded.
+    pub fn exists(&self) -> bool {
+        match self {
+            RecentBatch::Files(paths) => paths.iter().any(|path| path.is_file()),
+            RecentBatch::Folder { path, .. } => path.is_dir(),
+            RecentBatch::Archive(path) => path.is_file(),
+        }
+    }
+
+    // The image paths to load, scanning a folder or listing an archive as it is now
+    pub fn image_paths(&self) -> Result<Vec<PathBuf>, String> {
+        match self {
+            RecentBatch::Files(paths) => Ok(paths.clone()),
+            RecentBatch::Folder { path, recursive } => Ok(scan::collect_images(path, *recursive)),
+            RecentBatch::Archive(path) => archive::image_paths(path).map_err(|e| e.to_string()),
+        }
+    }
+
+    // Where output is saved by default, as when the batch was first opened
+    pub fn save_folder(&self) -> PathBuf {
+        let parent = match self {
+            RecentBatch::Folder { path, .. } => return path.clone(),
+            RecentBatch::Files(paths) => paths.first().and_then(|path| path.parent()),
+            RecentBatch::Archive(path) => path.parent(),
+        };
+        parent
+            .map(Path::to_path_buf)
+            .unwrap_or_else(|| PathBuf::from("."))
+    }
+}
@@ -0,0 +1,96 @@
+use crate::has_supported_extension;
+use std::cmp::Ordering;
+use std::collections::HashSet;
+use std::path::{Path, PathBuf};
+
+// How many folder levels below the chosen one a recursive scan descends
+const MAX_SCAN_DEPTH: usize = 8;
+
+// Collect every supported image in `root`, and in its subfolders when `recursive` is set,
+// sorted by natural path order so `ch2/p10.jpg` comes after `ch2/p9.jpg` and before `ch10/p1.jpg`.
+pub fn collect_images(root: &Path, recursive: bool) -> Vec<PathBuf> {
+    let max_depth = if recursive { MAX_SCAN_DEPTH } else { 0 };
+    let mut visited = HashSet::new();
+    let mut found = Vec::new();
+    walk(root, 0, max_depth, &mut visited, &mut found);
+    found.sort_by(|a, b| natural_cmp(&a.to_string_lossy(), &b.to_string_lossy()));
+    found
+}
+
+fn walk(
+    dir: &Path,
+    depth: usize,
+    max_depth: usize,
+    visited: &mut HashSet<PathBuf>,
+    found: &mut Vec<PathBuf>,
+) {
+    // Symlinked folders can point back up the tree, so each real folder is only read once
+    let Ok(canonical) = dir.canonicalize() else {
+        return;
+    };
+    if !visited.insert(canonical) {
+        return;
+    }
+    let Ok(entries) = std::fs::read_dir(dir) else {
+        return;
+    };
+
+    for entry in entries.flatten() {
+        let path = entry.path();
+        // Follows symlinks, so linked files and folders are treated like the real thing
+        let Ok(metadata) = std::fs::metadata(&path) else {
+            continue;
+        };
+        if metadata.is_dir() {
+            if depth < max_depth {
+                walk(&path, depth + 1, max_depth, visited, found);
+            }
+        } else if metadata.is_file() && has_supported_extension(&path) {
+            found.push(path);
+        }
+    }
+}
+
+// Compare strings the way people read them: runs of digits by value, everything else case-insensitively
+pub fn natural_cmp(a: &str, b: &str) -> Ordering {
+    let mut a_chars = a.chars().peekable();
+    let mut b_chars = b.chars().peekable();
+
+    loop {
+        match (a_chars.peek().copied(), b_chars.peek().copied()) {
+            (None, None) => return a.cmp(b),
+            (None, Some(_)) => return Ordering::Less,
+            (Some(_), None) => return Ordering::Greater,
+            (Some(x), Some(y)) if x.is_ascii_digit() && y.is_ascii_digit() => {
+                let x_num = take_digits(&mut a_chars);
+                let y_num = take_digits(&mut b_chars);
+                let x_trimmed = x_num.trim_start_matches('0');
+                let y_trimmed = y_num.trim_start_matches('0');
+                // Without leading zeros, a longer run is a bigger number
+                let ordering = x_trimmed
+                    .len()
+                    .cmp(&y_trimmed.len())
+                    .then_with(|| x_trimmed.cmp(y_trimmed));
+                if ordering != Ordering::Equal {
+                    return ordering;
+                }
+            }
+            (Some(x), Some(y)) => {
+                let ordering = x.to_lowercase().cmp(y.to_lowercase());
+                if ordering != Ordering::Equal {
+                    return ordering;
+                }
+                a_chars.next();
+                b_chars.next();
+            }
+        }
+    }
+}
+
+fn take_digits(chars: &mut std::iter::Peekable<std::str::Chars<'_>>) -> String {
+    let mut digits = String::new();
+    while let Some(c) = chars.next_if(|c| c.is_ascii_digit()) {
+        digits.push(c);
+    }
+    digits
+}
@@ -0,0 +1,145 @@
+use crate::recent::{RecentBatch, MAX_RECENT};
+use crate::{
+    clamp_quality, OutputOptions, PreviewFit, ThumbnailOptions, ViewMode, DEFAULT_CARD_SIZE,
+    DEFAULT_DPI, DEFAULT_LARGE_IMAGE_MEGAPIXELS, DEFAULT_SUBFOLDER, DEFAULT_THUMBNAIL_QUALITY,
+    HIRES_THUMBNAIL_SIZE, MAX_CARD_SIZE, MAX_GUTTER_WINDOW, MAX_LARGE_IMAGE_MEGAPIXELS,
+    MAX_OVERLAP, MAX_PADDING, MAX_SPLITS, MAX_THREADS, MAX_TONE_ADJUSTMENT, MIN_CARD_SIZE,
+    THUMBNAIL_SIZE,
+};
+use serde::{Deserialize, Serialize};
+use std::path::PathBuf;
+
+const SETTINGS_FILE: &str = "settings.json";
+
+// User preferences that survive restarts, stored as JSON in the OS config directory
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Settings {
+    pub output: OutputOptions,
+    // Preview card width in CSS pixels
+    pub card_size: u32,
+    pub view_mode: ViewMode,
+    pub hires_previews: bool,
+    pub preview_fit: PreviewFit,
+    // Whether FOLDER also picks up images in subfolders
+    pub recursive_scan: bool,
+    // Separate from the output quality; lower values make the in-memory previews smaller
+    pub thumbnail_quality: u8,
+    // Megapixels above which previews use the memory-saving decode
+    pub large_image_megapixels: u32,
+    // Reveal the output folder in the file manager after a run
+    pub open_output: bool,
+    // Where the last run was saved; the save dialog starts there
+    pub last_save_folder: Option<PathBuf>,
+    // Save straight to `last_save_folder` without asking
+    pub reuse_save_folder: bool,
+    // Move the start number past each finished run, so separate runs into one folder don't collide
+    pub continue_numbering: bool,
+    // Announce a finished run through the OS notification centre, for batches left running
+    pub notify_when_done: bool,
+    // Batches opened before, newest first
+    pub recent: Vec<RecentBatch>,
+}
+
+impl Default for Settings {
+    fn default() -> Self {
+        Self {
+            output: OutputOptions::default(),
+            card_size: DEFAULT_CARD_SIZE,
+            view_mode: ViewMode::Grid,
+            hires_previews: false,
+            preview_fit: PreviewFit::Contain,
+            recursive_scan: true,
+            thumbnail_quality: DEFAULT_THUMBNAIL_QUALITY,
+            large_image_megapixels: DEFAULT_LARGE_IMAGE_MEGAPIXELS,
+            open_output: false,
+            last_save_folder: None,
+            reuse_save_folder: false,
+            continue_numbering: false,
+            notify_when_done: false,
+            recent: Vec::new(),
+        }
+    }
+}
+
+impl Settings {
+    // Load the saved settings, falling back to defaults when the file is missing or unreadable
+    pub fn load() -> Self {
+        config_path()
+            .and_then(|path| std::fs::read_to_string(path).ok())
+            .and_then(|contents| serde_json::from_str::<Settings>(&contents).ok())
+            .map(Settings::sanitized)
+            .unwrap_or_default()
+    }
+
+    pub fn save(&self) -> Result<(), Box<dyn std::error::Error>> {
+        let path = config_path().ok_or("No config directory available")?;
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        std::fs::write(path, serde_json::to_string_pretty(self)?)?;
+        Ok(())
+    }
+
+    // Put `batch` at the top of the recent list, moving it there if it was already listed
+    pub fn remember(&mut self, batch: RecentBatch) {
+        self.recent.retain(|listed| *listed != batch);
+        self.recent.insert(0, batch);
+        self.recent.truncate(MAX_RECENT);
+    }
+
+    // How thumbnails are generated: resolution, JPEG quality, orientation handling and fit
+    pub fn thumbnail_options(&self) -> ThumbnailOptions {
+        let size = if self.hires_previews {
+            HIRES_THUMBNAIL_SIZE
+        } else {
+            THUMBNAIL_SIZE
+        };
+        ThumbnailOptions {
+            size,
+            quality: self.thumbnail_quality,
+            auto_orient: self.output.auto_orient,
+            brightness: self.output.brightness,
+            contrast: self.output.contrast,
+            background: self.output.background,
+            large_image_megapixels: self.large_image_megapixels,
+            fit: self.preview_fit,
+        }
+    }
+
+    // A hand-edited file can hold values the UI would never produce, so pull them back into range
+    pub fn sanitized(mut self) -> Self {
+        self.output.quality = clamp_quality(self.output.quality as i64);
+        self.thumbnail_quality = clamp_quality(self.thumbnail_quality as i64);
+        self.output.padding = self.output.padding.clamp(1, MAX_PADDING);
+        self.output.splits = self.output.splits.clamp(2, MAX_SPLITS);
+        if self.output.dpi == 0 {
+            self.output.dpi = DEFAULT_DPI;
+        }
+        if self.output.subfolder.trim().is_empty() {
+            self.output.subfolder = DEFAULT_SUBFOLDER.to_string();
+        }
+        self.output.threads = self.output.threads.min(MAX_THREADS);
+        self.output.overlap = self.output.overlap.min(MAX_OVERLAP);
+        self.output.gutter_window = self.output.gutter_window.clamp(1, MAX_GUTTER_WINDOW);
+        self.output.max_dimension = self.output.max_dimension.max(1);
+        self.output.brightness = self
+            .output
+            .brightness
+            .clamp(-MAX_TONE_ADJUSTMENT, MAX_TONE_ADJUSTMENT);
+        self.output.contrast = self
+            .output
+            .contrast
+            .clamp(-MAX_TONE_ADJUSTMENT, MAX_TONE_ADJUSTMENT);
+        self.card_size = self.card_size.clamp(MIN_CARD_SIZE, MAX_CARD_SIZE);
+        self.large_image_megapixels = self
+            .large_image_megapixels
+            .clamp(1, MAX_LARGE_IMAGE_MEGAPIXELS);
+        self.recent.truncate(MAX_RECENT);
+        self
+    }
+}
+
+fn config_path() -> Option<PathBuf> {
+    dirs::config_dir().map(|dir| dir.join("irs").join(SETTINGS_FILE))
+}
@@ -0,0 +1,113 @@
+use crate::archive::{self, Source};
+use crate::error::IrsError;
+use std::path::Path;
+use tiff::decoder::{Decoder, DecodingResult};
+use tiff::tags::Tag;
+use tiff::ColorType;
+
+// How many images (IFDs) the file holds. Only the directories are walked, no pixels are read.
+// Anything that isn't a readable TIFF, JPEGs included, counts as a single page. Inside an
+// archive only `.tif`/`.tiff` entries are looked at, so other images aren't decompressed twice.
+pub fn page_count(path: &Path) -> usize {
+    let tiff_name = path
+        .extension()
+        .is_some_and(|ext| ext.eq_ignore_ascii_case("tif") || ext.eq_ignore_ascii_case("tiff"));
+    if !tiff_name && archive::split(path).is_some() {
+        return 1;
+    }
+    let Ok(mut decoder) = open(path) else {
+        return 1;
+    };
+    let mut count = 1;
+    while decoder.more_images() && decoder.next_image().is_ok() {
+        count += 1;
+    }
+    count
+}
+
+// Pixel size of page `page` (0-based) and its own orientation tag (1-8, 1 when missing)
+pub fn dimensions(path: &Path, page: usize) -> Result<(u32, u32, u32), IrsError> {
+    let mut decoder = open(path)?;
+    decoder.seek_to_image(page).map_err(IrsError::decode)?;
+    let (width, height) = decoder.dimensions().map_err(IrsError::decode)?;
+    Ok((width, height, orientation(&mut decoder)))
+}
+
+// Decode page `page` (0-based), returning the pixels and the page's orientation tag.
+// image's TIFF decoder only ever reads the first page, so this goes through the tiff crate.
+pub fn decode(path: &Path, page: usize) -> Result<(image::DynamicImage, u32), IrsError> {
+    use image::DynamicImage;
+
+    let mut decoder = open(path)?;
+    decoder.seek_to_image(page).map_err(IrsError::decode)?;
+    let (width, height) = decoder.dimensions().map_err(IrsError::decode)?;
+    let color_type = decoder.colortype().map_err(IrsError::decode)?;
+    let orientation = orientation(&mut decoder);
+    let truncated = || IrsError::decode(format!("Page {} of the TIFF is truncated", page + 1));
+
+    let img = match (color_type, decoder.read_image().map_err(IrsError::decode)?) {
+        (ColorType::Gray(8), DecodingResult::U8(data)) => DynamicImage::ImageLuma8(
+            image::GrayImage::from_raw(width, height, data).ok_or_else(truncated)?,
+        ),
+        (ColorType::GrayA(8), DecodingResult::U8(data)) => DynamicImage::ImageLumaA8(
+            image::GrayAlphaImage::from_raw(width, height, data).ok_or_else(truncated)?,
+        ),
+        (ColorType::RGB(8), DecodingResult::U8(data)) => DynamicImage::ImageRgb8(
+            image::RgbImage::from_raw(width, height, data).ok_or_else(truncated)?,
+        ),
+        (ColorType::RGBA(8), DecodingResult::U8(data)) => DynamicImage::ImageRgba8(
+            image::RgbaImage::from_raw(width, height, data).ok_or_else(truncated)?,
+        ),
+        // Converted the same naive way image does for the first page, so all pages match
+        (ColorType::CMYK(8), DecodingResult::U8(data)) => {
+            let rgb = data
+                .chunks_exact(4)
+                .flat_map(|cmyk| {
+                    let k = 255 - cmyk[3] as u16;
+                    cmyk[..3]
+                        .iter()
+                        .map(move |&c| ((255 - c as u16) * k / 255) as u8)
+                })
+                .collect();
+            DynamicImage::ImageRgb8(
+                image::RgbImage::from_raw(width, height, rgb).ok_or_else(truncated)?,
+            )
+        }
+        (ColorType::Gray(16), DecodingResult::U16(data)) => DynamicImage::ImageLuma16(
+            image::ImageBuffer::from_raw(width, height, data).ok_or_else(truncated)?,
+        ),
+        (ColorType::GrayA(16), DecodingResult::U16(data)) => DynamicImage::ImageLumaA16(
+            image::ImageBuffer::from_raw(width, height, data).ok_or_else(truncated)?,
+        ),
+        (ColorType::RGB(16), DecodingResult::U16(data)) => DynamicImage::ImageRgb16(
+            image::ImageBuffer::from_raw(width, height, data).ok_or_else(truncated)?,
+        ),
+        (ColorType::RGBA(16), DecodingResult::U16(data)) => DynamicImage::ImageRgba16(
+            image::ImageBuffer::from_raw(width, height, data).ok_or_else(truncated)?,
+        ),
+        (color_type, _) => {
+            return Err(IrsError::decode(format!(
+                "Unsupported colour type on page {} of the TIFF: {:?}",
+                page + 1,
+                color_type
+            )))
+        }
+    };
+    Ok((img, orientation))
+}
+
+// Read through `archive::open`, so a TIFF inside a ZIP expands into its pages too
+fn open(path: &Path) -> Result<Decoder<Box<dyn Source>>, IrsError> {
+    Decoder::new(archive::open(path)?).map_err(IrsError::decode)
+}
+
+// Each page carries its own baseline Orientation tag, unlike EXIF which only describes the first
+fn orientation(decoder: &mut Decoder<Box<dyn Source>>) -> u32 {
+    decoder
+        .find_tag(Tag::Orientation)
+        .ok()
+        .flatten()
+        .and_then(|value| value.into_u32().ok())
+        .filter(|orientation| (1..=8).contains(orientation))
+        .unwrap_or(1)
+}